@@ -0,0 +1,27 @@
+use crate::types::error::Error;
+use crate::types::event::Event;
+use crate::types::join_request::JoinRequest;
+use candid::Principal;
+use ic_cdk::{query, update};
+
+// Organizers only: accept a pending request, the user joins if there is room
+#[update]
+async fn approve_join_request(event_id: u64, user_id: Principal) -> Result<(), Error> {
+    Event::approve_join_request(event_id, ic_cdk::api::msg_caller(), user_id).await
+}
+
+// Organizers only: decline a pending request, the reason is sent to the user
+#[update]
+fn reject_join_request(event_id: u64, user_id: Principal, reason: String) -> Result<(), Error> {
+    Event::reject_join_request(event_id, ic_cdk::api::msg_caller(), user_id, reason)
+}
+
+#[update]
+fn cancel_join_request(event_id: u64) -> Result<(), Error> {
+    Event::cancel_join_request(event_id, ic_cdk::api::msg_caller())
+}
+
+#[query]
+fn get_join_requests(event_id: u64) -> Result<Vec<JoinRequest>, Error> {
+    Event::get_join_requests(event_id, ic_cdk::api::msg_caller())
+}
@@ -0,0 +1,33 @@
+use crate::types::attendance::{Attendance, AttendanceStats, CheckInCode};
+use crate::types::error::Error;
+use crate::types::event::Event;
+use candid::Principal;
+use ic_cdk::{query, update};
+
+// Organizers only: start check-in, from one hour before the event until it ends
+#[update]
+async fn open_check_in(event_id: u64) -> Result<CheckInCode, Error> {
+    Event::open_check_in(event_id, ic_cdk::api::msg_caller()).await
+}
+
+// Organizers only: the code participants enter, it changes every minute
+#[query]
+fn get_check_in_code(event_id: u64) -> Result<CheckInCode, Error> {
+    Event::check_in_code(event_id, ic_cdk::api::msg_caller())
+}
+
+#[update]
+fn check_in(event_id: u64, code: String) -> Result<(), Error> {
+    Event::check_in(event_id, ic_cdk::api::msg_caller(), code)
+}
+
+// Organizers only: who showed up
+#[query]
+fn get_attendance(event_id: u64) -> Result<Vec<Attendance>, Error> {
+    Event::get_attendance(event_id, ic_cdk::api::msg_caller())
+}
+
+#[query]
+fn get_user_attendance(user_id: Principal) -> AttendanceStats {
+    AttendanceStats::get(user_id)
+}
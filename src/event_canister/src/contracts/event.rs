@@ -1,6 +1,9 @@
+use crate::services::scheduler;
+use crate::services::visibility::{validate_visibility, Viewer};
 use crate::types::event::{CreateEventInput, Event, EventUpdate};
-use crate::types::filter::EventFilter;
-use crate::types::response::EventResponse;
+use crate::types::filter::{EventCursor, EventFilter, EventSort};
+use crate::types::response::{EventPage, EventResponse, EventSummary, NearbyEvent};
+use crate::utils::get_user_free_days;
 use candid::Principal;
 use ic_cdk::{query, update};
 use crate::types::error::Error;
@@ -12,13 +15,17 @@ async fn create_event(event_data: CreateEventInput) -> Result<EventResponse, Err
 }
 
 #[update]
-fn update_event(event_id: u64, updated_data: EventUpdate) -> Result<(), Error> {
-    Event::update(event_id, updated_data)
+async fn update_event(event_id: u64, updated_data: EventUpdate) -> Result<(), Error> {
+    let caller = ic_cdk::api::msg_caller();
+    validate_visibility(updated_data.visibility.as_ref(), caller).await?;
+    let group_admin = Event::is_managed_by_group_admin(event_id, caller).await?;
+    Event::update(event_id, updated_data, group_admin)
 }
 
 #[update]
-fn delete_event(event_id: u64) -> Result<(), Error> {
-    Event::delete(event_id)
+async fn delete_event(event_id: u64) -> Result<(), Error> {
+    let group_admin = Event::is_managed_by_group_admin(event_id, ic_cdk::api::msg_caller()).await?;
+    Event::delete(event_id, group_admin)
 }
 
 #[update]
@@ -33,28 +40,133 @@ fn leave_event(event_id: u64) -> Result<(), Error> {
     Event::leave(event_id, user_id)
 }
 
-#[query]
-fn get_event(event_id: u64) -> Option<EventResponse> {
-    Event::get_by_id(event_id).map(EventResponse::from)
+#[update]
+async fn join_waitlist(event_id: u64) -> Result<u32, Error> {
+    let user_id = ic_cdk::api::msg_caller();
+    if let Some(event) = Event::get_by_id(event_id) {
+        event.check_join_access(user_id).await?;
+    }
+    Event::join_waitlist(event_id, user_id)
 }
 
-#[query]
-fn get_event_participants(event_id: u64) -> Result<Vec<Principal>, Error> {
-    Event::get_participants(event_id)
+#[update]
+fn leave_waitlist(event_id: u64) -> Result<(), Error> {
+    let user_id = ic_cdk::api::msg_caller();
+    Event::leave_waitlist(event_id, user_id)
 }
 
-#[query]
-fn get_all_events() -> Vec<EventResponse> {
+// Composite queries: group-only events ask the social canister for the
+// caller's groups, and matching `on_free_days` asks the user canister
+#[query(composite = true)]
+async fn get_event(event_id: u64) -> Option<EventResponse> {
+    let viewer = Viewer::caller().await;
+    Event::get_by_id(event_id)
+        .filter(|event| event.is_visible_to(&viewer))
+        .map(EventResponse::from)
+}
+
+// Composite query: group-only events ask the social canister
+#[query(composite = true)]
+async fn get_group_events(group_id: String) -> Vec<EventSummary> {
+    let viewer = Viewer::caller().await;
+    Event::get_group_events(&group_id, &viewer)
+        .into_iter()
+        .map(EventSummary::from)
+        .collect()
+}
+
+// Composite query: group-only events ask the social canister
+#[query(composite = true)]
+async fn get_event_participants(event_id: u64) -> Result<Vec<Principal>, Error> {
+    let viewer = Viewer::caller().await;
+    Event::get_participants(event_id, &viewer)
+}
+
+#[query(composite = true)]
+async fn get_all_events() -> Vec<EventResponse> {
+    let viewer = Viewer::caller().await;
     Event::get_all()
         .into_iter()
+        .filter(|event| event.is_listed_for(&viewer))
         .map(EventResponse::from)
         .collect()
 }
 
-#[query]
-fn filter_events(filter: EventFilter) -> Vec<EventResponse> {
-    Event::filter_events(filter)
+#[query(composite = true)]
+async fn filter_events(filter: EventFilter) -> Vec<EventResponse> {
+    let free_days = caller_free_days(&filter).await;
+    let viewer = Viewer::caller().await;
+    Event::filter_events(filter, free_days.as_deref(), &viewer)
         .into_iter()
         .map(EventResponse::from)
         .collect()
 }
+
+#[query(composite = true)]
+async fn list_events(
+    filter: EventFilter,
+    sort: Option<EventSort>,
+    cursor: Option<EventCursor>,
+    limit: Option<u32>,
+) -> EventPage {
+    let free_days = caller_free_days(&filter).await;
+    let viewer = Viewer::caller().await;
+    Event::list(
+        filter,
+        free_days.as_deref(),
+        &viewer,
+        sort.unwrap_or_default(),
+        cursor,
+        limit,
+    )
+}
+
+// Events within `radius_km` of the given coordinates, nearest first
+#[query(composite = true)]
+async fn events_near(
+    latitude: f64,
+    longitude: f64,
+    radius_km: f64,
+    filter: EventFilter,
+) -> Result<Vec<NearbyEvent>, Error> {
+    let free_days = caller_free_days(&filter).await;
+    let viewer = Viewer::caller().await;
+    Event::near(
+        latitude,
+        longitude,
+        radius_km,
+        filter,
+        free_days.as_deref(),
+        &viewer,
+    )
+}
+
+// None when the filter does not need them, the caller has not set any or the
+// user canister could not be reached, the criterion is then ignored
+async fn caller_free_days(filter: &EventFilter) -> Option<Vec<u8>> {
+    if filter.on_free_days != Some(true) {
+        return None;
+    }
+    get_user_free_days(ic_cdk::api::msg_caller())
+        .await
+        .ok()
+        .flatten()
+}
+
+#[query]
+fn get_reminder_offsets() -> Vec<u64> {
+    scheduler::get_reminder_offsets()
+}
+
+// Controllers only: reminder offsets in seconds before the event date
+#[update]
+fn set_reminder_offsets(offsets: Vec<u64>) -> Result<(), Error> {
+    if !ic_cdk::api::is_controller(&ic_cdk::api::msg_caller()) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only controllers can change the reminder offsets".to_string(),
+        });
+    }
+    scheduler::set_reminder_offsets(offsets)
+}
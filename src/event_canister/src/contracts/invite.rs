@@ -0,0 +1,34 @@
+use crate::types::error::Error;
+use crate::types::event::Event;
+use crate::types::invite::EventInvites;
+use crate::types::response::EventResponse;
+use candid::Principal;
+use ic_cdk::{query, update};
+
+// Organizers only: a shareable code for an invite-only event
+#[update]
+async fn create_event_invite_code(event_id: u64) -> Result<String, Error> {
+    Event::create_invite_code(event_id, ic_cdk::api::msg_caller()).await
+}
+
+#[update]
+fn delete_event_invite_code(event_id: u64, code: String) -> Result<(), Error> {
+    Event::delete_invite_code(event_id, code, ic_cdk::api::msg_caller())
+}
+
+// Organizers only: invite users directly
+#[update]
+fn invite_to_event(event_id: u64, users: Vec<Principal>) -> Result<(), Error> {
+    Event::invite(event_id, ic_cdk::api::msg_caller(), users)
+}
+
+// Redeem an invite code, the event can then be seen and joined
+#[update]
+fn accept_event_invite(event_id: u64, code: String) -> Result<EventResponse, Error> {
+    Event::accept_invite(event_id, ic_cdk::api::msg_caller(), code).map(EventResponse::from)
+}
+
+#[query]
+fn get_event_invites(event_id: u64) -> Result<EventInvites, Error> {
+    Event::get_invites(event_id, ic_cdk::api::msg_caller())
+}
@@ -0,0 +1,27 @@
+use crate::types::error::Error;
+use crate::types::match_result::{MatchResult, MatchResultInput, SportStats};
+use candid::Principal;
+use ic_cdk::{query, update};
+
+// Organizers only: scores and player stats of a completed event
+#[update]
+fn submit_match_result(event_id: u64, input: MatchResultInput) -> Result<MatchResult, Error> {
+    MatchResult::submit(event_id, ic_cdk::api::msg_caller(), input)
+}
+
+// Participants only: the result is not counted until the organizers correct it
+#[update]
+fn dispute_match_result(event_id: u64, reason: String) -> Result<(), Error> {
+    MatchResult::dispute(event_id, ic_cdk::api::msg_caller(), reason)
+}
+
+#[query]
+fn get_match_result(event_id: u64) -> Option<MatchResult> {
+    MatchResult::get(event_id)
+}
+
+// Per-sport totals of a user, for their profile
+#[query]
+fn get_player_stats(user_id: Principal) -> Vec<SportStats> {
+    SportStats::get_for_user(user_id)
+}
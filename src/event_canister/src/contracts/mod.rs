@@ -1,2 +1,12 @@
+pub mod approval;
+pub mod attendance;
 pub mod event;
+pub mod invite;
+pub mod match_result;
+pub mod organizer;
+pub mod payment;
 pub mod review;
+pub mod quarantine;
+pub mod series;
+pub mod skill;
+pub mod team;
@@ -0,0 +1,38 @@
+use crate::types::error::Error;
+use crate::types::event::Event;
+use crate::types::organizer::OrganizerRight;
+use candid::Principal;
+use ic_cdk::update;
+
+// Creator only: give a user the rights to help run the event
+#[update]
+fn add_co_organizer(
+    event_id: u64,
+    user_id: Principal,
+    rights: Vec<OrganizerRight>,
+) -> Result<(), Error> {
+    Event::add_co_organizer(event_id, ic_cdk::api::msg_caller(), user_id, rights)
+}
+
+// Creator only, or the co-organizer stepping down
+#[update]
+fn remove_co_organizer(event_id: u64, user_id: Principal) -> Result<(), Error> {
+    Event::remove_co_organizer(event_id, ic_cdk::api::msg_caller(), user_id)
+}
+
+// Creator only: the new owner takes over once they accept
+#[update]
+fn transfer_event_ownership(event_id: u64, new_owner: Principal) -> Result<(), Error> {
+    Event::transfer_ownership(event_id, ic_cdk::api::msg_caller(), new_owner)
+}
+
+#[update]
+async fn accept_event_ownership(event_id: u64) -> Result<(), Error> {
+    Event::accept_ownership(event_id, ic_cdk::api::msg_caller()).await
+}
+
+// The creator withdraws a pending transfer, or the new owner declines it
+#[update]
+fn cancel_event_ownership_transfer(event_id: u64) -> Result<(), Error> {
+    Event::cancel_ownership_transfer(event_id, ic_cdk::api::msg_caller())
+}
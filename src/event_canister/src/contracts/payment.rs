@@ -0,0 +1,32 @@
+use crate::services::escrow;
+use crate::types::error::Error;
+use crate::types::event::Event;
+use crate::types::payment::EscrowTransfer;
+use ic_cdk::{query, update};
+
+// Retry the refunds and payouts of an event, returns how many were paid.
+// Settlement also runs on its own when an event is cancelled or completed
+#[update]
+async fn settle_event_payments(event_id: u64) -> Result<u32, Error> {
+    let caller = ic_cdk::api::msg_caller();
+    let event = Event::get_by_id(event_id).ok_or(Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    })?;
+
+    if event.creator_id != caller && !ic_cdk::api::is_controller(&caller) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only creator can settle event payments".to_string(),
+        });
+    }
+    escrow::settle(event_id).await
+}
+
+// Deposits, refunds and payouts of an event, participants only see their own
+#[query]
+fn get_event_payments(event_id: u64) -> Result<Vec<EscrowTransfer>, Error> {
+    escrow::get_transfers(event_id, ic_cdk::api::msg_caller())
+}
@@ -1,5 +1,7 @@
 use crate::types::error::Error;
+use crate::types::rating::RatingSummary;
 use crate::types::review::Review;
+use candid::Principal;
 use ic_cdk::{query, update};
 
 #[update]
@@ -7,6 +9,16 @@ async fn review_event(event_id: u64, rating: u8, comment: Option<String>) -> Res
     Review::new(event_id, rating, comment).await
 }
 
+#[update]
+fn update_review(review_id: u64, rating: u8, comment: Option<String>) -> Result<(), Error> {
+    Review::update(review_id, rating, comment)
+}
+
+#[update]
+fn delete_review(review_id: u64) -> Result<(), Error> {
+    Review::delete(review_id)
+}
+
 #[query]
 fn get_event_reviews(event_id: u64) -> Vec<Review> {
     Review::get_reviews_for_event(event_id)
@@ -16,3 +28,8 @@ fn get_event_reviews(event_id: u64) -> Vec<Review> {
 fn get_review(review_id: u64) -> Option<Review> {
     Review::get_by_id(review_id)
 }
+
+#[query]
+fn get_organizer_reputation(organizer: Principal) -> RatingSummary {
+    RatingSummary::for_organizer(organizer)
+}
@@ -0,0 +1,46 @@
+use crate::services::visibility::{validate_visibility, Viewer};
+use crate::types::error::Error;
+use crate::types::event::EventUpdate;
+use crate::types::response::EventSummary;
+use crate::types::series::{CreateEventSeriesInput, EventSeries, SeriesScope};
+use ic_cdk::{query, update};
+
+#[update]
+async fn create_event_series(input: CreateEventSeriesInput) -> Result<EventSeries, Error> {
+    EventSeries::new(input).await
+}
+
+// Composite query: group-only occurrences ask the social canister
+#[query(composite = true)]
+async fn get_event_series(series_id: u64) -> Option<EventSeries> {
+    let viewer = Viewer::caller().await;
+    EventSeries::get_by_id(series_id).filter(|series| series.is_visible_to(&viewer))
+}
+
+// Composite query: group-only occurrences ask the social canister
+#[query(composite = true)]
+async fn get_series_events(series_id: u64) -> Vec<EventSummary> {
+    let viewer = Viewer::caller().await;
+    EventSeries::get_events(series_id)
+        .into_iter()
+        .filter(|event| event.is_visible_to(&viewer))
+        .map(EventSummary::from)
+        .collect()
+}
+
+// Edit one occurrence of a series, or it and every later one
+#[update]
+async fn update_event_series(
+    event_id: u64,
+    updated_data: EventUpdate,
+    scope: SeriesScope,
+) -> Result<(), Error> {
+    validate_visibility(updated_data.visibility.as_ref(), ic_cdk::api::msg_caller()).await?;
+    EventSeries::update_occurrences(event_id, updated_data, scope)
+}
+
+// Cancel one occurrence of a series, or end the series with it
+#[update]
+fn cancel_event_series(event_id: u64, scope: SeriesScope) -> Result<(), Error> {
+    EventSeries::cancel_occurrences(event_id, scope)
+}
@@ -0,0 +1,16 @@
+use crate::types::skill::{RatingChange, SkillRating};
+use crate::types::sport::Sports;
+use candid::Principal;
+use ic_cdk::query;
+
+// Per-sport ratings of a user, updated from their confirmed match results
+#[query]
+fn get_skill_ratings(user_id: Principal) -> Vec<SkillRating> {
+    SkillRating::get_for_user(user_id)
+}
+
+// Rating after every rated match of the sport, oldest first
+#[query]
+fn get_rating_history(user_id: Principal, sport: Sports) -> Vec<RatingChange> {
+    SkillRating::get_history(user_id, &sport)
+}
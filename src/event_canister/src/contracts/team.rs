@@ -0,0 +1,32 @@
+use crate::types::error::Error;
+use crate::types::event::Event;
+use crate::types::team::{Team, TeamStrategy};
+use candid::Principal;
+use ic_cdk::update;
+
+// Organizers only: split the participants of a team-sport event, every
+// participant is told their team
+#[update]
+async fn generate_teams(
+    event_id: u64,
+    team_count: u8,
+    strategy: TeamStrategy,
+) -> Result<Vec<Team>, Error> {
+    Event::generate_teams(event_id, ic_cdk::api::msg_caller(), team_count, strategy).await
+}
+
+// Organizers only: exchange two players of different teams
+#[update]
+fn swap_team_players(
+    event_id: u64,
+    player: Principal,
+    other_player: Principal,
+) -> Result<Vec<Team>, Error> {
+    Event::swap_team_players(event_id, ic_cdk::api::msg_caller(), player, other_player)
+}
+
+// Participants only: who the caller wants to play with
+#[update]
+fn set_preferred_teammates(event_id: u64, teammates: Vec<Principal>) -> Result<(), Error> {
+    Event::set_preferred_teammates(event_id, ic_cdk::api::msg_caller(), teammates)
+}
@@ -3,18 +3,47 @@ mod middleware;
 mod services;
 mod storage;
 mod types;
+mod upgrade;
 mod utils;
 
 use crate::types::{
+    attendance::{Attendance, AttendanceStats, CheckInCode},
     error::Error,
     event::{CreateEventInput, EventUpdate},
-    filter::EventFilter,
-    response::EventResponse,
+    filter::{EventCursor, EventFilter, EventSort},
+    invite::EventInvites,
+    join_request::JoinRequest,
+    match_result::{MatchResult, MatchResultInput, SportStats},
+    organizer::OrganizerRight,
+    payment::EscrowTransfer,
+    quarantine::QuarantinedRecord,
+    rating::RatingSummary,
+    response::{EventPage, EventResponse, EventSummary, NearbyEvent},
     review::Review,
+    series::{CreateEventSeriesInput, EventSeries, SeriesScope},
+    skill::{RatingChange, SkillRating},
+    sport::Sports,
+    team::{Team, TeamStrategy},
 };
 
 use candid::Principal;
 use ic_cdk;
+use ic_cdk::{init, post_upgrade, pre_upgrade};
+
+#[init]
+fn init() {
+    upgrade::init();
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    upgrade::pre_upgrade();
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    upgrade::post_upgrade();
+}
 
 // Export contracts
 ic_cdk::export_candid!();
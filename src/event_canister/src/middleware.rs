@@ -24,7 +24,8 @@ fn is_update_method(method_name: &str) -> bool {
     // * Check if method follows update patterns
     let update_patterns = [
         "set_", "update_", "create_", "delete_", "add_", "remove_", "join_", "leave_", "save_",
-        "review_",
+        "review_", "cancel_", "settle_", "open_", "check_in", "invite_", "accept_", "approve_",
+        "reject_", "transfer", "submit_", "dispute_",
     ];
 
     update_patterns
@@ -0,0 +1,250 @@
+use crate::services::escrow;
+use crate::storage::{EVENTS, JOIN_REQUESTS};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::join_request::JoinRequest;
+use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::organizer::OrganizerRight;
+use crate::types::response::nanoseconds_to_seconds;
+use crate::types::skill::SkillRating;
+use crate::utils::notify_in_background;
+use candid::Principal;
+use ic_cdk::api::time;
+
+const MAX_REASON_LENGTH: usize = 500;
+
+fn event_not_found() -> Error {
+    Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    }
+}
+
+fn has_request(event_id: u64, user_id: Principal) -> bool {
+    JOIN_REQUESTS.with(|requests| requests.borrow().contains_key(&(event_id, user_id)))
+}
+
+fn remove_request(event_id: u64, user_id: Principal) -> Option<u64> {
+    JOIN_REQUESTS.with(|requests| requests.borrow_mut().remove(&(event_id, user_id)))
+}
+
+fn request_not_found() -> Error {
+    Error {
+        code: 404,
+        error: "Join request not found".to_string(),
+        message: "No pending join request from this user".to_string(),
+    }
+}
+
+impl Event {
+    pub fn requires_approval(&self) -> bool {
+        self.requires_approval.unwrap_or_default()
+    }
+
+    // `join` of an event in approval mode, the organizers are asked to review
+    pub fn request_to_join(&self, user_id: Principal) -> Result<(), Error> {
+        if self.status != EventStatus::Upcoming {
+            return Err(Error {
+                code: 400,
+                error: "Invalid event status".to_string(),
+                message: "Cannot join event that is not upcoming".to_string(),
+            });
+        }
+        if self.participants.contains(&user_id) {
+            return Err(Error {
+                code: 400,
+                error: "User already joined".to_string(),
+                message: "User is already a participant of the event".to_string(),
+            });
+        }
+        if has_request(self.id, user_id) {
+            return Err(Error {
+                code: 409,
+                error: "Request already sent".to_string(),
+                message: "User already asked to join the event".to_string(),
+            });
+        }
+
+        JOIN_REQUESTS.with(|requests| requests.borrow_mut().insert((self.id, user_id), time()));
+        notify_in_background(
+            self.organizers_with(&OrganizerRight::ManageParticipants),
+            NewNotification {
+                content: format!("Someone asked to join your event: {}", self.title),
+                notification_type: NotificationType::Message,
+            },
+        );
+        Ok(())
+    }
+
+    // Organizers only: capacity and payment are checked now, not when the request was sent
+    pub async fn approve_join_request(
+        event_id: u64,
+        caller: Principal,
+        user_id: Principal,
+    ) -> Result<(), Error> {
+        let event = Self::get_for_review(event_id, caller)?;
+        if !has_request(event_id, user_id) {
+            return Err(request_not_found());
+        }
+
+        EVENTS.with(|events| {
+            let mut events_map = events.borrow_mut();
+            let mut event = events_map.get(&event_id).ok_or_else(event_not_found)?;
+            if event.participants.contains(&user_id) {
+                return Err(Error {
+                    code: 400,
+                    error: "User already joined".to_string(),
+                    message: "User is already a participant of the event".to_string(),
+                });
+            }
+            if event.is_full() {
+                return Err(Error {
+                    code: 409,
+                    error: "Maximum participants reached".to_string(),
+                    message: "Event is full, raise the limit or reject the request".to_string(),
+                });
+            }
+            event.participants.push(user_id);
+            event.waitlist.retain(|&waiting| waiting != user_id);
+            event.updated_at = time();
+            events_map.insert(event_id, event);
+            Ok(())
+        })?;
+        let requested_at = remove_request(event_id, user_id);
+
+        if let Some(cost) = event.paid_cost() {
+            if let Err(e) = escrow::collect(event_id, user_id, cost).await {
+                Event::drop_participant(event_id, user_id);
+                if let Some(requested_at) = requested_at {
+                    JOIN_REQUESTS.with(|requests| {
+                        requests
+                            .borrow_mut()
+                            .insert((event_id, user_id), requested_at)
+                    });
+                }
+                return Err(e);
+            }
+        }
+        SkillRating::seed_in_background(vec![user_id], event.sport.clone());
+
+        notify_in_background(
+            vec![user_id],
+            NewNotification {
+                content: format!(
+                    "Your request was approved, you have joined the event: {}",
+                    event.title
+                ),
+                notification_type: NotificationType::Message,
+            },
+        );
+        Ok(())
+    }
+
+    // Organizers only: the reason is sent to the user
+    pub fn reject_join_request(
+        event_id: u64,
+        caller: Principal,
+        user_id: Principal,
+        reason: String,
+    ) -> Result<(), Error> {
+        let event = Self::get_for_review(event_id, caller)?;
+
+        let reason = reason.trim();
+        if reason.is_empty() || reason.chars().count() > MAX_REASON_LENGTH {
+            return Err(Error {
+                code: 400,
+                error: "Invalid reason".to_string(),
+                message: format!(
+                    "A reason of at most {} characters is required",
+                    MAX_REASON_LENGTH
+                ),
+            });
+        }
+
+        remove_request(event_id, user_id).ok_or_else(request_not_found)?;
+        notify_in_background(
+            vec![user_id],
+            NewNotification {
+                content: format!(
+                    "Your request to join the event {} was rejected: {}",
+                    event.title, reason
+                ),
+                notification_type: NotificationType::Alert,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn cancel_join_request(event_id: u64, user_id: Principal) -> Result<(), Error> {
+        remove_request(event_id, user_id)
+            .map(|_| ())
+            .ok_or_else(request_not_found)
+    }
+
+    // Organizers only: pending requests, oldest first
+    pub fn get_join_requests(event_id: u64, caller: Principal) -> Result<Vec<JoinRequest>, Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if !event.has_right(caller, &OrganizerRight::ManageParticipants) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only organizers managing participants can review join requests"
+                    .to_string(),
+            });
+        }
+
+        let mut requests = pending_requests(event_id);
+        requests.sort_by_key(|request| request.requested_at);
+        Ok(requests)
+    }
+
+    // Drop the pending requests of a cancelled or deleted event, returns who had one
+    pub fn remove_join_requests(event_id: u64) -> Vec<Principal> {
+        let users: Vec<Principal> = pending_requests(event_id)
+            .into_iter()
+            .map(|request| request.user_id)
+            .collect();
+        JOIN_REQUESTS.with(|requests| {
+            let mut requests = requests.borrow_mut();
+            for user_id in &users {
+                requests.remove(&(event_id, *user_id));
+            }
+        });
+        users
+    }
+
+    fn get_for_review(event_id: u64, caller: Principal) -> Result<Event, Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if !event.has_right(caller, &OrganizerRight::ManageParticipants) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only organizers managing participants can review join requests"
+                    .to_string(),
+            });
+        }
+        if event.status != EventStatus::Upcoming {
+            return Err(Error {
+                code: 400,
+                error: "Invalid event status".to_string(),
+                message: "Join requests can only be reviewed before the event starts".to_string(),
+            });
+        }
+        Ok(event)
+    }
+}
+
+fn pending_requests(event_id: u64) -> Vec<JoinRequest> {
+    JOIN_REQUESTS.with(|requests| {
+        requests
+            .borrow()
+            .range((event_id, Principal::management_canister())..)
+            .take_while(|entry| entry.key().0 == event_id)
+            .map(|entry| JoinRequest {
+                user_id: entry.key().1,
+                requested_at: nanoseconds_to_seconds(entry.value()),
+            })
+            .collect()
+    })
+}
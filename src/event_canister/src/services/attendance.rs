@@ -0,0 +1,346 @@
+use crate::storage::{ATTENDANCE, CHECK_IN_SESSIONS, USER_ATTENDANCE};
+use crate::types::attendance::{Attendance, AttendanceStats, CheckInCode, CheckInSession};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::organizer::OrganizerRight;
+use crate::types::response::nanoseconds_to_seconds;
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_cdk::management_canister::raw_rand;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+// Check-in can be opened this long before the event starts
+const CHECK_IN_OPENS_BEFORE: u64 = 60 * 60 * NANOS_PER_SECOND;
+// A code is accepted for its own period and the next one, so one shown
+// just before it changed still works
+const CODE_ROTATION: u64 = 60 * NANOS_PER_SECOND;
+const MAX_FAILED_ATTEMPTS: u8 = 5;
+
+// Wrong codes per (event, user), so codes cannot be guessed. Kept on the heap,
+// an upgrade just gives everyone their attempts back
+thread_local! {
+    static FAILED_ATTEMPTS: RefCell<BTreeMap<(u64, Principal), u8>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+impl CheckInSession {
+    fn period(&self, now: u64) -> u64 {
+        now.saturating_sub(self.opened_at) / CODE_ROTATION
+    }
+
+    // Six digit code of a rotation period
+    fn code(&self, period: u64) -> String {
+        let hash = Sha256::new()
+            .chain_update(&self.seed)
+            .chain_update(period.to_be_bytes())
+            .finalize();
+        let value = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]);
+        format!("{:06}", value % 1_000_000)
+    }
+
+    fn current_code(&self, now: u64) -> CheckInCode {
+        let period = self.period(now);
+        let expires_at = self
+            .opened_at
+            .saturating_add((period + 1) * CODE_ROTATION)
+            .min(self.closes_at);
+        CheckInCode {
+            code: self.code(period),
+            expires_at: nanoseconds_to_seconds(expires_at),
+        }
+    }
+
+    fn accepts(&self, code: &str, now: u64) -> bool {
+        let period = self.period(now);
+        self.code(period) == code || (period > 0 && self.code(period - 1) == code)
+    }
+}
+
+impl AttendanceStats {
+    pub fn get(user_id: Principal) -> AttendanceStats {
+        USER_ATTENDANCE
+            .with(|stats| stats.borrow().get(&user_id))
+            .unwrap_or_default()
+    }
+}
+
+fn event_not_found() -> Error {
+    Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    }
+}
+
+fn get_session(event_id: u64) -> Result<CheckInSession, Error> {
+    CHECK_IN_SESSIONS
+        .with(|sessions| sessions.borrow().get(&event_id))
+        .ok_or(Error {
+            code: 404,
+            error: "Check-in not open".to_string(),
+            message: "The organizer has not opened check-in for this event".to_string(),
+        })
+}
+
+// Only organizers with the check-in right run the check-in of an event
+fn get_organized_event(event_id: u64, caller: Principal) -> Result<Event, Error> {
+    let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+    if !event.has_right(caller, &OrganizerRight::CheckIn) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only organizers running the check-in can manage it".to_string(),
+        });
+    }
+    Ok(event)
+}
+
+impl Event {
+    // Open check-in around the event date, opening again keeps the same session
+    pub async fn open_check_in(event_id: u64, caller: Principal) -> Result<CheckInCode, Error> {
+        let event = get_organized_event(event_id, caller)?;
+
+        let now = time();
+        if matches!(
+            event.status,
+            EventStatus::Completed | EventStatus::Cancelled
+        ) || now.saturating_add(CHECK_IN_OPENS_BEFORE) < event.event_date
+        {
+            return Err(Error {
+                code: 400,
+                error: "Check-in not available".to_string(),
+                message: "Check-in opens one hour before the event and closes when it ends"
+                    .to_string(),
+            });
+        }
+
+        if let Ok(session) = get_session(event_id) {
+            return Ok(session.current_code(now));
+        }
+
+        let seed = raw_rand().await.map_err(|e| Error {
+            code: 500,
+            error: format!("Randomness unavailable: {}", e),
+            message: "Failed to open check-in, please try again".to_string(),
+        })?;
+
+        // The event may have been opened or changed while waiting for randomness
+        let now = time();
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        let session = CHECK_IN_SESSIONS.with(|sessions| {
+            let mut sessions = sessions.borrow_mut();
+            if let Some(session) = sessions.get(&event_id) {
+                return session;
+            }
+            let session = CheckInSession {
+                seed,
+                opened_at: now,
+                closes_at: event.ends_at(),
+            };
+            sessions.insert(event_id, session.clone());
+            session
+        });
+        Ok(session.current_code(now))
+    }
+
+    // Code to show participants right now
+    pub fn check_in_code(event_id: u64, caller: Principal) -> Result<CheckInCode, Error> {
+        get_organized_event(event_id, caller)?;
+        Ok(get_session(event_id)?.current_code(time()))
+    }
+
+    pub fn check_in(event_id: u64, user_id: Principal, code: String) -> Result<(), Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        let session = get_session(event_id)?;
+
+        let now = time();
+        if now > session.closes_at {
+            return Err(Error {
+                code: 400,
+                error: "Check-in closed".to_string(),
+                message: "The event has ended".to_string(),
+            });
+        }
+
+        if !event.participants.contains(&user_id) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "User is not a participant of the event".to_string(),
+            });
+        }
+
+        if ATTENDANCE.with(|attendance| attendance.borrow().contains_key(&(event_id, user_id))) {
+            return Err(Error {
+                code: 409,
+                error: "Already checked in".to_string(),
+                message: "User already checked in to the event".to_string(),
+            });
+        }
+
+        let failed = FAILED_ATTEMPTS.with(|attempts| {
+            attempts
+                .borrow()
+                .get(&(event_id, user_id))
+                .copied()
+                .unwrap_or(0)
+        });
+        if failed >= MAX_FAILED_ATTEMPTS {
+            return Err(Error {
+                code: 429,
+                error: "Too many attempts".to_string(),
+                message: "Too many wrong codes were entered for this event".to_string(),
+            });
+        }
+
+        if !session.accepts(code.trim(), now) {
+            FAILED_ATTEMPTS.with(|attempts| {
+                attempts
+                    .borrow_mut()
+                    .insert((event_id, user_id), failed + 1);
+            });
+            return Err(Error {
+                code: 400,
+                error: "Invalid code".to_string(),
+                message: "The check-in code is wrong or expired".to_string(),
+            });
+        }
+
+        ATTENDANCE.with(|attendance| attendance.borrow_mut().insert((event_id, user_id), now));
+        FAILED_ATTEMPTS.with(|attempts| attempts.borrow_mut().remove(&(event_id, user_id)));
+        Ok(())
+    }
+
+    // Every participant, with their check-in time if they checked in
+    pub fn get_attendance(event_id: u64, caller: Principal) -> Result<Vec<Attendance>, Error> {
+        let event = get_organized_event(event_id, caller)?;
+
+        Ok(ATTENDANCE.with(|attendance| {
+            let attendance = attendance.borrow();
+            event
+                .participants
+                .iter()
+                .map(|&user_id| Attendance {
+                    user_id,
+                    checked_in_at: attendance
+                        .get(&(event_id, user_id))
+                        .map(nanoseconds_to_seconds),
+                })
+                .collect()
+        }))
+    }
+
+    // Called once the event completed. Events that never opened check-in
+    // are not counted, nobody could have checked in
+    pub fn record_attendance(&self) {
+        let opened = CHECK_IN_SESSIONS.with(|sessions| sessions.borrow().contains_key(&self.id));
+        if !opened {
+            return;
+        }
+        Event::close_check_in(self.id);
+
+        ATTENDANCE.with(|attendance| {
+            let attendance = attendance.borrow();
+            USER_ATTENDANCE.with(|stats| {
+                let mut stats = stats.borrow_mut();
+                for &user_id in &self.participants {
+                    let mut user_stats = stats.get(&user_id).unwrap_or_default();
+                    if attendance.contains_key(&(self.id, user_id)) {
+                        user_stats.attended += 1;
+                    } else {
+                        user_stats.no_shows += 1;
+                    }
+                    stats.insert(user_id, user_stats);
+                }
+            });
+        });
+    }
+
+    // Follow a new date or duration of the event, an open check-in closes
+    // again when the event moved out of the check-in window
+    pub fn refresh_check_in(&self) {
+        let Ok(mut session) = get_session(self.id) else {
+            return;
+        };
+        if time().saturating_add(CHECK_IN_OPENS_BEFORE) < self.event_date {
+            Event::close_check_in(self.id);
+            return;
+        }
+        session.closes_at = self.ends_at();
+        CHECK_IN_SESSIONS.with(|sessions| sessions.borrow_mut().insert(self.id, session));
+    }
+
+    // Drop the check-in of a cancelled or deleted event
+    pub fn close_check_in(event_id: u64) {
+        CHECK_IN_SESSIONS.with(|sessions| sessions.borrow_mut().remove(&event_id));
+        FAILED_ATTEMPTS.with(|attempts| attempts.borrow_mut().retain(|(id, _), _| *id != event_id));
+    }
+
+    // Forget who checked in to a deleted event, the user counters are kept
+    pub fn remove_attendance(event_id: u64) {
+        ATTENDANCE.with(|attendance| {
+            let mut attendance = attendance.borrow_mut();
+            let checked_in: Vec<(u64, Principal)> = attendance
+                .keys_range((event_id, Principal::management_canister())..)
+                .take_while(|(id, _)| *id == event_id)
+                .collect();
+            for key in checked_in {
+                attendance.remove(&key);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPENED_AT: u64 = 1_000 * NANOS_PER_SECOND;
+
+    fn session(closes_at: u64) -> CheckInSession {
+        CheckInSession {
+            seed: vec![7; 32],
+            opened_at: OPENED_AT,
+            closes_at,
+        }
+    }
+
+    #[test]
+    fn accepts_the_current_and_previous_code() {
+        let session = session(OPENED_AT + 60 * CODE_ROTATION);
+        let now = OPENED_AT + 3 * CODE_ROTATION + 5 * NANOS_PER_SECOND;
+
+        let current = session.current_code(now).code;
+        assert_eq!(current, session.code(3));
+        assert!(session.accepts(&current, now));
+        assert!(session.accepts(&session.code(2), now));
+        assert!(!session.accepts(&session.code(1), now));
+        assert!(!session.accepts(&session.code(4), now));
+    }
+
+    #[test]
+    fn first_period_has_no_previous_code() {
+        let session = session(OPENED_AT + 60 * CODE_ROTATION);
+        assert!(session.accepts(&session.code(0), OPENED_AT));
+        assert_eq!(session.period(OPENED_AT.saturating_sub(1)), 0);
+    }
+
+    #[test]
+    fn code_expires_at_the_end_of_its_period() {
+        let session = session(OPENED_AT + 60 * CODE_ROTATION);
+        let code = session.current_code(OPENED_AT + CODE_ROTATION + 1);
+        assert_eq!(
+            code.expires_at,
+            nanoseconds_to_seconds(OPENED_AT + 2 * CODE_ROTATION)
+        );
+    }
+
+    #[test]
+    fn expiry_is_capped_at_the_close() {
+        let closes_at = OPENED_AT + CODE_ROTATION / 2;
+        let code = session(closes_at).current_code(OPENED_AT + 1);
+        assert_eq!(code.expires_at, nanoseconds_to_seconds(closes_at));
+    }
+}
@@ -0,0 +1,276 @@
+use crate::storage::{ESCROW_DEPOSITS, ESCROW_TRANSFERS, EVENTS};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::payment::{EscrowDeposit, EscrowTransfer, TransferKind};
+use crate::utils::{ledger_fee, ledger_transfer, ledger_transfer_from, notify_in_background};
+use candid::Principal;
+use ic_cdk::api::{canister_self, time};
+use ic_cdk::futures::spawn;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use std::time::Duration;
+
+// Participants leaving at least this long before the event get their deposit back
+const REFUND_DEADLINE: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+impl Event {
+    // Amount escrowed for each participant, in ledger units
+    pub fn paid_cost(&self) -> Option<u64> {
+        self.cost_per_person.filter(|&cost| cost > 0)
+    }
+
+    // Release the spot of a participant whose payment failed. The spot is
+    // not given to the waitlist, as it was never really taken
+    pub fn drop_participant(event_id: u64, user_id: Principal) {
+        EVENTS.with(|events| {
+            let mut events_map = events.borrow_mut();
+            if let Some(mut event) = events_map.get(&event_id) {
+                event
+                    .participants
+                    .retain(|&participant| participant != user_id);
+                event.remove_from_teams(user_id);
+                event.updated_at = time();
+                events_map.insert(event_id, event);
+            }
+        });
+    }
+}
+
+// Every event escrow is a subaccount of this canister,
+// with the event ID (big-endian) in its last 8 bytes
+pub fn escrow_subaccount(event_id: u64) -> Subaccount {
+    let mut subaccount = [0u8; 32];
+    subaccount[24..].copy_from_slice(&event_id.to_be_bytes());
+    subaccount
+}
+
+fn memo(event_id: u64) -> Vec<u8> {
+    format!("tal3a event {}", event_id).into_bytes()
+}
+
+fn get_deposit(event_id: u64, user_id: Principal) -> Option<EscrowDeposit> {
+    ESCROW_DEPOSITS.with(|deposits| deposits.borrow().get(&(event_id, user_id)))
+}
+
+fn get_deposits(event_id: u64) -> Vec<(Principal, EscrowDeposit)> {
+    ESCROW_DEPOSITS.with(|deposits| {
+        deposits
+            .borrow()
+            .range((event_id, Principal::management_canister())..)
+            .take_while(|entry| entry.key().0 == event_id)
+            .map(|entry| (entry.key().1, entry.value()))
+            .collect()
+    })
+}
+
+pub fn has_deposits(event_id: u64) -> bool {
+    !get_deposits(event_id).is_empty()
+}
+
+fn record(transfer: EscrowTransfer) {
+    ESCROW_TRANSFERS.with(|transfers| {
+        transfers
+            .borrow_mut()
+            .insert((transfer.event_id, transfer.block_index), transfer);
+    });
+}
+
+// Pull the event cost from a participant who just took a spot. The caller
+// must have approved this canister on the ledger for the cost plus the fee.
+// Deposits that arrive after the event finished are settled right away
+pub async fn collect(event_id: u64, user_id: Principal, amount: u64) -> Result<(), Error> {
+    // A deposit not refunded yet (left and joined again) is kept
+    if let Some(mut deposit) = get_deposit(event_id, user_id) {
+        deposit.forfeited = false;
+        ESCROW_DEPOSITS.with(|deposits| deposits.borrow_mut().insert((event_id, user_id), deposit));
+        return Ok(());
+    }
+
+    let escrow = Account {
+        owner: canister_self(),
+        subaccount: Some(escrow_subaccount(event_id)),
+    };
+    let block_index = ledger_transfer_from(user_id, escrow, amount, memo(event_id)).await?;
+
+    let now = time();
+    ESCROW_DEPOSITS.with(|deposits| {
+        deposits.borrow_mut().insert(
+            (event_id, user_id),
+            EscrowDeposit {
+                amount,
+                block_index,
+                forfeited: false,
+                created_at: now,
+            },
+        )
+    });
+    record(EscrowTransfer {
+        event_id,
+        kind: TransferKind::Deposit,
+        participant: user_id,
+        recipient: canister_self(),
+        amount,
+        block_index,
+        created_at: now,
+    });
+
+    // The event may have been cancelled or completed while the ledger was
+    // called, its settlement then ran without this deposit
+    let finished = Event::get_by_id(event_id).is_some_and(|event| {
+        matches!(
+            event.status,
+            EventStatus::Completed | EventStatus::Cancelled
+        )
+    });
+    if finished {
+        settle_in_background(event_id);
+    }
+    Ok(())
+}
+
+// Charge people promoted from the waitlist, those whose payment fails lose the spot
+pub fn collect_in_background(event_id: u64, users: Vec<Principal>, amount: u64) {
+    if users.is_empty() {
+        return;
+    }
+
+    ic_cdk_timers::set_timer(Duration::from_nanos(1), move || {
+        spawn(async move {
+            let mut unpaid = Vec::new();
+            for user_id in users {
+                if collect(event_id, user_id, amount).await.is_err() {
+                    Event::drop_participant(event_id, user_id);
+                    unpaid.push(user_id);
+                }
+            }
+            notify_in_background(
+                unpaid,
+                NewNotification {
+                    content: "Your payment for an event failed, so your spot was released"
+                        .to_string(),
+                    notification_type: NotificationType::Alert,
+                },
+            );
+        });
+    });
+}
+
+// Called after `user_id` left `event`: refund before the deadline, forfeit after it
+pub fn on_leave(event: &Event, user_id: Principal) {
+    let Some(mut deposit) = get_deposit(event.id, user_id) else {
+        return;
+    };
+
+    if time().saturating_add(REFUND_DEADLINE) > event.event_date {
+        deposit.forfeited = true;
+        ESCROW_DEPOSITS.with(|deposits| deposits.borrow_mut().insert((event.id, user_id), deposit));
+    } else {
+        settle_in_background(event.id);
+    }
+}
+
+pub fn settle_in_background(event_id: u64) {
+    ic_cdk_timers::set_timer(Duration::from_nanos(1), move || {
+        spawn(async move {
+            let _ = settle(event_id).await;
+        });
+    });
+}
+
+// Pay out every deposit the event allows: refunds on cancellation and for
+// people who left in time, the rest goes to the creator once completed.
+// Only the cost is deposited, so every payout is short by the ledger fee.
+// Failed transfers keep their deposit, so settling again retries them
+pub async fn settle(event_id: u64) -> Result<u32, Error> {
+    let Some(event) = Event::get_by_id(event_id) else {
+        return Ok(0);
+    };
+
+    let payouts: Vec<(Principal, EscrowDeposit, TransferKind, Principal)> = get_deposits(event_id)
+        .into_iter()
+        .filter_map(|(user_id, deposit)| {
+            let refund = event.status == EventStatus::Cancelled
+                || (!deposit.forfeited && !event.participants.contains(&user_id));
+            if refund {
+                Some((user_id, deposit, TransferKind::Refund, user_id))
+            } else if event.status == EventStatus::Completed {
+                Some((user_id, deposit, TransferKind::Release, event.creator_id))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if payouts.is_empty() {
+        return Ok(0);
+    }
+
+    // The escrow pays the ledger fee of every payout
+    let fee = ledger_fee().await?;
+    let mut settled = 0;
+    let mut last_error = None;
+    for (user_id, deposit, kind, recipient) in payouts {
+        // Claim the deposit before awaiting, so concurrent settlements pay it once
+        let claimed =
+            ESCROW_DEPOSITS.with(|deposits| deposits.borrow_mut().remove(&(event_id, user_id)));
+        if claimed.is_none() {
+            continue;
+        }
+
+        let amount = deposit.amount.saturating_sub(fee);
+        if amount == 0 {
+            settled += 1;
+            continue;
+        }
+
+        match ledger_transfer(
+            escrow_subaccount(event_id),
+            recipient,
+            amount,
+            memo(event_id),
+        )
+        .await
+        {
+            Ok(block_index) => {
+                record(EscrowTransfer {
+                    event_id,
+                    kind,
+                    participant: user_id,
+                    recipient,
+                    amount,
+                    block_index,
+                    created_at: time(),
+                });
+                settled += 1;
+            }
+            Err(e) => {
+                ESCROW_DEPOSITS
+                    .with(|deposits| deposits.borrow_mut().insert((event_id, user_id), deposit));
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) => Err(e),
+        None => Ok(settled),
+    }
+}
+
+// Creator and controllers see every transfer, participants only their own
+pub fn get_transfers(event_id: u64, caller: Principal) -> Result<Vec<EscrowTransfer>, Error> {
+    let event = Event::get_by_id(event_id).ok_or(Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    })?;
+    let sees_all = event.creator_id == caller || ic_cdk::api::is_controller(&caller);
+
+    Ok(ESCROW_TRANSFERS.with(|transfers| {
+        transfers
+            .borrow()
+            .range((event_id, 0)..=(event_id, u64::MAX))
+            .map(|entry| entry.value())
+            .filter(|transfer| sees_all || transfer.participant == caller)
+            .collect()
+    }))
+}
@@ -1,19 +1,41 @@
+use crate::services::group::validate_group;
+use crate::services::visibility::{validate_visibility, Viewer};
+use crate::services::{escrow, index, scheduler};
 use crate::storage::EVENTS;
 use crate::types::error::Error;
 use crate::types::event::{CreateEventInput, Event, EventStatus, EventUpdate};
 use crate::types::filter::CostFilter;
-use crate::types::filter::EventFilter;
+use crate::types::filter::{EventCursor, EventFilter, EventSort};
+use crate::types::match_result::MatchResult;
 use crate::types::notification::{NewNotification, NotificationType};
-use crate::types::response::seconds_to_nanoseconds;
-use crate::utils::{add_notification, generate_unique_id, get_city};
+use crate::types::organizer::OrganizerRight;
+use crate::types::rating::RatingSummary;
+use crate::types::response::{
+    nanoseconds_to_seconds, seconds_to_nanoseconds, EventPage, EventSummary,
+};
+use crate::types::skill::SkillRating;
+use crate::utils::{
+    add_notification, generate_unique_id, get_city, normalize_search_text, notify_in_background,
+};
 use candid::Principal;
 use ic_cdk::api::time;
 
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+// Egypt standard time (UTC+2), used to tell which day an event falls on
+pub const CAIRO_UTC_OFFSET_SECONDS: u64 = 2 * 60 * 60;
+
 impl Event {
     pub async fn new(input: CreateEventInput) -> Result<Event, Error> {
         let caller = ic_cdk::api::msg_caller();
+        Self::validate_input(&input).await?;
 
-        // Validate input data
+        // Convert frontend seconds to nanoseconds
+        let event_date_nanos = seconds_to_nanoseconds(input.event_date);
+        Ok(Self::create(caller, input, event_date_nanos, None).await)
+    }
+
+    pub async fn validate_input(input: &CreateEventInput) -> Result<(), Error> {
         if input.title.trim().is_empty() {
             return Err(Error {
                 code: 400,
@@ -29,43 +51,53 @@ impl Event {
                 message: "Event location description cannot be empty".to_string(),
             });
         }
+        input.location.validate_coordinates()?;
+        validate_visibility(input.visibility.as_ref(), ic_cdk::api::msg_caller()).await?;
+        validate_group(input.group_id.as_ref(), ic_cdk::api::msg_caller()).await?;
 
         // Validate Location city and governorate using inter canister call
-        let city_data = get_city(
-            input.location.city.clone(),
-            input.location.governorate.clone(),
-        )
-        .await;
-
-        if let Err(e) = city_data {
-            return Err(e);
-        }
+        get_city(input.location.city, input.location.governorate).await?;
 
-        // Convert frontend seconds to nanoseconds for comparison
-        let event_date_nanos = seconds_to_nanoseconds(input.event_date);
-        if event_date_nanos <= time() {
+        if seconds_to_nanoseconds(input.event_date) <= time() {
             return Err(Error {
                 code: 400,
                 error: "Invalid event date".to_string(),
                 message: "Event date must be in the future".to_string(),
             });
         }
+        Ok(())
+    }
 
+    // Store a new event from validated input, `event_date` is in nanoseconds
+    pub async fn create(
+        creator_id: Principal,
+        input: CreateEventInput,
+        event_date: u64,
+        series_id: Option<u64>,
+    ) -> Event {
         // Use random ID using raw_rand
         let event_id = generate_unique_id().await;
 
         let now = time();
         let new_event = Event {
             id: event_id,
-            creator_id: caller,
+            creator_id,
             title: input.title,
             description: input.description,
-            event_date: event_date_nanos,
+            event_date,
             duration_hours: input.duration_hours,
             location: input.location,
             sport: input.sport,
             max_participants: input.max_participants,
             participants: vec![], // Creator is not added to participants
+            waitlist: vec![],
+            series_id,
+            visibility: input.visibility,
+            requires_approval: input.requires_approval,
+            group_id: input.group_id,
+            co_organizers: None,
+            pending_owner: None,
+            teams: None,
             status: EventStatus::Upcoming,
             images: input.images,
             cost_per_person: input.cost_per_person,
@@ -77,89 +109,81 @@ impl Event {
         EVENTS.with(|events| {
             events.borrow_mut().insert(event_id, new_event.clone());
         });
+        scheduler::reschedule(event_id, None, new_event.next_transition_at());
+        scheduler::reschedule_reminders(event_id, None, new_event.reminders_anchor());
+        index::reindex(None, Some(&new_event));
 
-        Ok(new_event)
+        new_event
     }
 
     pub fn get_by_id(event_id: u64) -> Option<Event> {
         EVENTS.with(|events| events.borrow().get(&event_id))
     }
 
-    pub fn update(event_id: u64, updated_data: EventUpdate) -> Result<(), Error> {
+    // `group_admin` when the caller manages the event as an admin of its group
+    pub fn update(
+        event_id: u64,
+        updated_data: EventUpdate,
+        group_admin: bool,
+    ) -> Result<(), Error> {
         let caller = ic_cdk::api::msg_caller();
 
         EVENTS.with(|events| {
             let mut events_map = events.borrow_mut();
 
             if let Some(mut event) = events_map.get(&event_id) {
-                // Check if caller is the creator
-                if event.creator_id != caller {
-                    return Err(Error {
-                        code: 403,
-                        error: "Forbidden".to_string(),
-                        message: "Only creator can update event".to_string(),
-                    });
-                }
+                let scheduled_at = event.next_transition_at();
+                let reminders_at = event.reminders_anchor();
+                let indexed = event.clone();
 
-                // Update fields if provided
-                if let Some(title) = updated_data.title {
-                    if title.trim().is_empty() {
-                        return Err(Error {
-                            code: 400,
-                            error: "Title cannot be empty".to_string(),
-                            message: "Event title cannot be empty".to_string(),
-                        });
-                    }
-                    event.title = title;
-                }
-                if let Some(description) = updated_data.description {
-                    event.description = Some(description);
-                }
-                if let Some(location) = updated_data.location {
-                    if location.description.trim().is_empty() {
-                        return Err(Error {
-                            code: 400,
-                            error: "Location description cannot be empty".to_string(),
-                            message: "Event location description cannot be empty".to_string(),
-                        });
-                    }
-                    event.location = location;
-                }
-                if let Some(event_date) = updated_data.event_date {
-                    let event_date_nanos = seconds_to_nanoseconds(event_date);
-                    if event_date_nanos <= time() {
-                        return Err(Error {
-                            code: 400,
-                            error: "Invalid event date".to_string(),
-                            message: "Event date must be in the future".to_string(),
-                        });
-                    }
-                    event.event_date = event_date_nanos;
-                }
-                if let Some(duration_hours) = updated_data.duration_hours {
-                    event.duration_hours = duration_hours;
-                }
-                if let Some(max_participants) = updated_data.max_participants {
-                    event.max_participants = Some(max_participants);
-                }
-                if let Some(sport) = updated_data.sport {
-                    event.sport = sport;
-                }
-                if let Some(status) = updated_data.status {
-                    event.status = status;
-                }
-                if let Some(images) = updated_data.images {
-                    event.images = images;
-                }
-                if let Some(cost_per_person) = updated_data.cost_per_person {
-                    event.cost_per_person = Some(cost_per_person);
-                }
-                if let Some(requirements) = updated_data.requirements {
-                    event.requirements = requirements;
+                event.apply_update(caller, updated_data, group_admin)?;
+
+                // Raising max_participants frees spots for the waitlist
+                let promoted = event.promote_waitlist();
+                if let Some(cost) = event.paid_cost() {
+                    escrow::collect_in_background(event_id, promoted.clone(), cost);
                 }
+                notify_in_background(
+                    promoted,
+                    NewNotification {
+                        content: format!(
+                            "A spot opened up, you have joined the event: {}",
+                            event.title
+                        ),
+                        notification_type: NotificationType::Message,
+                    },
+                );
 
                 event.updated_at = time();
-                events_map.insert(event_id, event);
+                scheduler::reschedule(event_id, scheduled_at, event.next_transition_at());
+                scheduler::reschedule_reminders(event_id, reminders_at, event.reminders_anchor());
+                index::reindex(Some(&indexed), Some(&event));
+                events_map.insert(event_id, event.clone());
+
+                if event.event_date != indexed.event_date
+                    || event.duration_hours != indexed.duration_hours
+                {
+                    event.refresh_check_in();
+                }
+
+                // Everyone who paid gets refunded, only once the update is saved
+                if event.status == EventStatus::Cancelled {
+                    escrow::settle_in_background(event_id);
+                    Event::close_check_in(event_id);
+                    Event::remove_teammate_requests(event_id);
+
+                    // Pending join requests are dropped with the cancellation
+                    let mut recipients = event.participants;
+                    recipients.extend(event.waitlist);
+                    recipients.extend(Event::remove_join_requests(event_id));
+                    notify_in_background(
+                        recipients,
+                        NewNotification {
+                            content: format!("The event {} has been cancelled", event.title),
+                            notification_type: NotificationType::Alert,
+                        },
+                    );
+                }
                 Ok(())
             } else {
                 Err(Error {
@@ -171,23 +195,194 @@ impl Event {
         })
     }
 
-    pub fn delete(event_id: u64) -> Result<(), Error> {
+    // Check an update against the event, without saving or notifying anything
+    pub fn validate_update(
+        event_id: u64,
+        update: &EventUpdate,
+        group_admin: bool,
+    ) -> Result<(), Error> {
+        let mut event = Self::get_by_id(event_id).ok_or(Error {
+            code: 404,
+            error: "Event not found".to_string(),
+            message: "No event found with the given ID".to_string(),
+        })?;
+        event.apply_update(ic_cdk::api::msg_caller(), update.clone(), group_admin)
+    }
+
+    // Validate the update and apply it to the fields, the caller saves the
+    // event and does the rest once it succeeded
+    fn apply_update(
+        &mut self,
+        caller: Principal,
+        update: EventUpdate,
+        group_admin: bool,
+    ) -> Result<(), Error> {
+        // Check if caller can edit the event or is a group admin
+        if !self.has_right(caller, &OrganizerRight::EditDetails) && !group_admin {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only organizers or group admins can update event".to_string(),
+            });
+        }
+
+        // Finished events are kept as they are
+        if matches!(self.status, EventStatus::Completed | EventStatus::Cancelled) {
+            return Err(Error {
+                code: 400,
+                error: "Invalid event status".to_string(),
+                message: "Cannot update an event that is completed or cancelled".to_string(),
+            });
+        }
+
+        // Update fields if provided
+        if let Some(title) = update.title {
+            if title.trim().is_empty() {
+                return Err(Error {
+                    code: 400,
+                    error: "Title cannot be empty".to_string(),
+                    message: "Event title cannot be empty".to_string(),
+                });
+            }
+            self.title = title;
+        }
+        if let Some(description) = update.description {
+            self.description = Some(description);
+        }
+        if let Some(location) = update.location {
+            if location.description.trim().is_empty() {
+                return Err(Error {
+                    code: 400,
+                    error: "Location description cannot be empty".to_string(),
+                    message: "Event location description cannot be empty".to_string(),
+                });
+            }
+            location.validate_coordinates()?;
+            self.location = location;
+        }
+        if let Some(event_date) = update.event_date {
+            if self.status != EventStatus::Upcoming {
+                return Err(Error {
+                    code: 400,
+                    error: "Invalid event status".to_string(),
+                    message: "Cannot change the date of an event that already started".to_string(),
+                });
+            }
+            let event_date_nanos = seconds_to_nanoseconds(event_date);
+            if event_date_nanos <= time() {
+                return Err(Error {
+                    code: 400,
+                    error: "Invalid event date".to_string(),
+                    message: "Event date must be in the future".to_string(),
+                });
+            }
+            self.event_date = event_date_nanos;
+        }
+        if let Some(duration_hours) = update.duration_hours {
+            self.duration_hours = duration_hours;
+        }
+        if let Some(max_participants) = update.max_participants {
+            self.max_participants = Some(max_participants);
+        }
+        if let Some(sport) = update.sport {
+            self.sport = sport;
+        }
+        // Other statuses are set by the scheduler
+        if let Some(status) = update.status {
+            // Co-organizers can edit the event but not call it off
+            if self.creator_id != caller && !group_admin {
+                return Err(Error {
+                    code: 403,
+                    error: "Forbidden".to_string(),
+                    message: "Only creator or group admins can cancel event".to_string(),
+                });
+            }
+            if status != EventStatus::Cancelled {
+                return Err(Error {
+                    code: 400,
+                    error: "Invalid event status".to_string(),
+                    message: "Event status can only be changed to Cancelled".to_string(),
+                });
+            }
+            self.status = status;
+        }
+        if let Some(images) = update.images {
+            self.images = images;
+        }
+        if let Some(cost_per_person) = update.cost_per_person {
+            // Deposits were taken at the old price
+            if escrow::has_deposits(self.id) {
+                return Err(Error {
+                    code: 409,
+                    error: "Event has deposits".to_string(),
+                    message: "Cannot change the cost after participants have paid".to_string(),
+                });
+            }
+            self.cost_per_person = Some(cost_per_person);
+        }
+        if let Some(requirements) = update.requirements {
+            self.requirements = requirements;
+        }
+        if let Some(visibility) = update.visibility {
+            self.visibility = Some(visibility);
+        }
+        if let Some(requires_approval) = update.requires_approval {
+            self.requires_approval = Some(requires_approval);
+        }
+        Ok(())
+    }
+
+    pub fn delete(event_id: u64, group_admin: bool) -> Result<(), Error> {
         let caller = ic_cdk::api::msg_caller();
 
         EVENTS.with(|events| {
             let mut events_map = events.borrow_mut();
 
             if let Some(event) = events_map.get(&event_id) {
-                // Check if caller is the creator
-                if event.creator_id != caller {
+                // Check if caller is the creator or a group admin
+                if event.creator_id != caller && !group_admin {
                     return Err(Error {
                         code: 403,
                         error: "Forbidden".to_string(),
-                        message: "Only creator can delete event".to_string(),
+                        message: "Only creator or group admins can delete event".to_string(),
+                    });
+                }
+
+                // Deleting would strand the escrowed fees, cancel the event instead
+                if escrow::has_deposits(event_id) {
+                    return Err(Error {
+                        code: 409,
+                        error: "Event has deposits".to_string(),
+                        message: "Cancel the event so participants are refunded first".to_string(),
                     });
                 }
 
                 events_map.remove(&event_id);
+                scheduler::reschedule(event_id, event.next_transition_at(), None);
+                scheduler::reschedule_reminders(event_id, event.reminders_anchor(), None);
+                index::reindex(Some(&event), None);
+                Event::close_check_in(event_id);
+                Event::remove_attendance(event_id);
+                Event::remove_invites(event_id);
+                let requesters = Event::remove_join_requests(event_id);
+                Event::remove_teammate_requests(event_id);
+                MatchResult::remove(event_id);
+                RatingSummary::remove_event(event_id, event.creator_id);
+
+                // Deleting an event that did not take place calls it off, tell
+                // the same people a cancellation does
+                if event.status == EventStatus::Upcoming {
+                    let mut recipients = event.participants.clone();
+                    recipients.extend(event.waitlist.iter().copied());
+                    recipients.extend(requesters);
+                    notify_in_background(
+                        recipients,
+                        NewNotification {
+                            content: format!("The event {} has been cancelled", event.title),
+                            notification_type: NotificationType::Alert,
+                        },
+                    );
+                }
                 Ok(())
             } else {
                 Err(Error {
@@ -200,6 +395,13 @@ impl Event {
     }
 
     pub async fn join(event_id: u64, user_id: Principal) -> Result<(), Error> {
+        if let Some(event) = Self::get_by_id(event_id) {
+            event.check_join_access(user_id).await?;
+            if event.requires_approval() {
+                return event.request_to_join(user_id);
+            }
+        }
+
         let mut event_opt = None;
         let mut event_title = String::new();
 
@@ -222,7 +424,7 @@ impl Event {
                         return Err(Error {
                             code: 400,
                             error: "Maximum participants reached".to_string(),
-                            message: "Cannot join event, maximum participants reached".to_string(),
+                            message: "Event is full, join the waitlist instead".to_string(),
                         });
                     }
                 }
@@ -237,6 +439,7 @@ impl Event {
                 }
 
                 event.participants.push(user_id.clone());
+                event.waitlist.retain(|&waiting| waiting != user_id);
                 event.updated_at = time();
                 event_title = event.title.clone();
                 event_opt = Some(event.clone());
@@ -256,6 +459,17 @@ impl Event {
             return Err(e);
         }
 
+        // The spot is reserved while the fee is taken, a failed payment releases it
+        if let Some(cost) = event_opt.as_ref().and_then(Event::paid_cost) {
+            if let Err(e) = escrow::collect(event_id, user_id, cost).await {
+                Event::drop_participant(event_id, user_id);
+                return Err(e);
+            }
+        }
+        if let Some(event) = &event_opt {
+            SkillRating::seed_in_background(vec![user_id], event.sport.clone());
+        }
+
         // Only send notification if event was successfully joined
         if let Some(_event) = event_opt {
             add_notification(
@@ -299,6 +513,25 @@ impl Event {
                     });
                 }
 
+                escrow::on_leave(&event, user_id);
+                event.remove_from_teams(user_id);
+
+                // Give the freed spot to the next person on the waitlist
+                let promoted = event.promote_waitlist();
+                if let Some(cost) = event.paid_cost() {
+                    escrow::collect_in_background(event_id, promoted.clone(), cost);
+                }
+                notify_in_background(
+                    promoted,
+                    NewNotification {
+                        content: format!(
+                            "A spot opened up, you have joined the event: {}",
+                            event.title
+                        ),
+                        notification_type: NotificationType::Message,
+                    },
+                );
+
                 event.updated_at = time();
                 events_map.insert(event_id, event);
                 Ok(())
@@ -312,9 +545,131 @@ impl Event {
         })
     }
 
-    pub fn get_participants(event_id: u64) -> Result<Vec<Principal>, Error> {
+    // Returns the caller's position on the waitlist (1 is next in line)
+    pub fn join_waitlist(event_id: u64, user_id: Principal) -> Result<u32, Error> {
+        EVENTS.with(|events| {
+            let mut events_map = events.borrow_mut();
+
+            let mut event = events_map.get(&event_id).ok_or(Error {
+                code: 404,
+                error: "Event not found".to_string(),
+                message: "No event found with the given ID".to_string(),
+            })?;
+
+            if event.status != EventStatus::Upcoming {
+                return Err(Error {
+                    code: 400,
+                    error: "Invalid event status".to_string(),
+                    message: "Cannot join the waitlist of an event that is not upcoming"
+                        .to_string(),
+                });
+            }
+
+            if event.participants.contains(&user_id) {
+                return Err(Error {
+                    code: 400,
+                    error: "User already joined".to_string(),
+                    message: "User is already a participant of the event".to_string(),
+                });
+            }
+
+            // The creator checks capacity when approving requests instead
+            if event.requires_approval() {
+                return Err(Error {
+                    code: 400,
+                    error: "Approval required".to_string(),
+                    message: "Ask to join the event, the organizer reviews every request"
+                        .to_string(),
+                });
+            }
+
+            if event.waitlist.contains(&user_id) {
+                return Err(Error {
+                    code: 400,
+                    error: "User already on waitlist".to_string(),
+                    message: "User is already on the waitlist of the event".to_string(),
+                });
+            }
+
+            if !event.is_full() {
+                return Err(Error {
+                    code: 409,
+                    error: "Event is not full".to_string(),
+                    message: "The event still has free spots, join it directly".to_string(),
+                });
+            }
+
+            event.waitlist.push(user_id);
+            event.updated_at = time();
+            let position = event.waitlist.len() as u32;
+            // Seeded now, promotions from the waitlist happen without an await
+            SkillRating::seed_in_background(vec![user_id], event.sport.clone());
+            events_map.insert(event_id, event);
+            Ok(position)
+        })
+    }
+
+    pub fn leave_waitlist(event_id: u64, user_id: Principal) -> Result<(), Error> {
+        EVENTS.with(|events| {
+            let mut events_map = events.borrow_mut();
+
+            let mut event = events_map.get(&event_id).ok_or(Error {
+                code: 404,
+                error: "Event not found".to_string(),
+                message: "No event found with the given ID".to_string(),
+            })?;
+
+            let initial_len = event.waitlist.len();
+            event.waitlist.retain(|&waiting| waiting != user_id);
+
+            if event.waitlist.len() == initial_len {
+                return Err(Error {
+                    code: 404,
+                    error: "User not found".to_string(),
+                    message: "User is not on the waitlist of the event".to_string(),
+                });
+            }
+
+            event.updated_at = time();
+            events_map.insert(event_id, event);
+            Ok(())
+        })
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.max_participants
+            .is_some_and(|max_participants| self.participants.len() >= max_participants as usize)
+    }
+
+    // Move people from the waitlist into free spots, oldest first
+    fn promote_waitlist(&mut self) -> Vec<Principal> {
+        if self.status != EventStatus::Upcoming {
+            return Vec::new();
+        }
+
+        let free_spots = match self.max_participants {
+            Some(max_participants) => {
+                (max_participants as usize).saturating_sub(self.participants.len())
+            }
+            None => self.waitlist.len(),
+        };
+
+        let promoted: Vec<Principal> = self
+            .waitlist
+            .drain(..free_spots.min(self.waitlist.len()))
+            .collect();
+        self.participants.extend(promoted.iter().copied());
+        promoted
+    }
+
+    // Events hidden from the viewer are reported as not found
+    pub fn get_participants(event_id: u64, viewer: &Viewer) -> Result<Vec<Principal>, Error> {
         EVENTS.with(|events| {
-            if let Some(event) = events.borrow().get(&event_id) {
+            if let Some(event) = events
+                .borrow()
+                .get(&event_id)
+                .filter(|event| event.is_visible_to(viewer))
+            {
                 Ok(event.participants)
             } else {
                 Err(Error {
@@ -330,28 +685,152 @@ impl Event {
         EVENTS.with(|events| events.borrow().values().collect::<Vec<Event>>())
     }
 
-    pub fn filter_events(filter: EventFilter) -> Vec<Event> {
-        // Get all events first, then filter them
-        let all_events = Self::get_all();
-
-        all_events
+    // `free_days` are the caller's free days, only used with `on_free_days`.
+    // Only events listed for `viewer` are returned
+    pub fn filter_events(
+        filter: EventFilter,
+        free_days: Option<&[u8]>,
+        viewer: &Viewer,
+    ) -> Vec<Event> {
+        let keywords = search_keywords(&filter);
+
+        // Narrow down with the index first, then check every field
+        index::find(&filter)
             .into_iter()
             .filter(|event| {
-                (filter.governorate.is_none()
-                    || Some(event.location.governorate) == filter.governorate)
-                    && (filter.city.is_none() || Some(event.location.city) == filter.city)
-                    && (filter.sport.is_none() || Some(event.sport.clone()) == filter.sport)
-                    && (filter.status.is_none() || Some(event.status.clone()) == filter.status)
-                    && (filter.cost_filter.is_none()
-                        || match filter.cost_filter.as_ref().unwrap() {
-                            CostFilter::Free => event.cost_per_person.is_none(),
-                            CostFilter::Paid => event.cost_per_person.is_some(),
-                            CostFilter::Range { min, max } => event
-                                .cost_per_person
-                                .map(|cost| cost >= *min && cost <= *max)
-                                .unwrap_or(false),
-                        })
+                event.is_listed_for(viewer) && event.matches_filter(&filter, &keywords, free_days)
             })
             .collect()
     }
+
+    // `keywords` come from `search_keywords(filter)`
+    pub fn matches_filter(
+        &self,
+        filter: &EventFilter,
+        keywords: &[String],
+        free_days: Option<&[u8]>,
+    ) -> bool {
+        (filter.governorate.is_none() || Some(self.location.governorate) == filter.governorate)
+            && (filter.city.is_none() || Some(self.location.city) == filter.city)
+            && (filter.sport.is_none() || Some(self.sport.clone()) == filter.sport)
+            && (filter.status.is_none() || Some(self.status.clone()) == filter.status)
+            && (filter.cost_filter.is_none()
+                || match filter.cost_filter.as_ref().unwrap() {
+                    CostFilter::Free => self.cost_per_person.is_none(),
+                    CostFilter::Paid => self.cost_per_person.is_some(),
+                    CostFilter::Range { min, max } => self
+                        .cost_per_person
+                        .map(|cost| cost >= *min && cost <= *max)
+                        .unwrap_or(false),
+                })
+            && filter.min_rating.is_none_or(|min_rating| {
+                let rating = RatingSummary::for_event(self.id);
+                rating.count > 0 && rating.average >= min_rating
+            })
+            && filter
+                .has_free_spots
+                .is_none_or(|has_free_spots| has_free_spots != self.is_full())
+            && (filter.on_free_days != Some(true)
+                || free_days.is_none_or(|days| days.contains(&self.weekday())))
+            && (keywords.is_empty() || self.matches_keywords(keywords))
+            && filter.skill_range.as_ref().is_none_or(|range| {
+                self.average_skill()
+                    .is_some_and(|skill| skill >= range.min && skill <= range.max)
+            })
+    }
+
+    // Day of the week in Cairo time, 0 = Sunday to 6 = Saturday like the
+    // `free_days` of the user profile
+    fn weekday(&self) -> u8 {
+        let local_seconds = nanoseconds_to_seconds(self.event_date) + CAIRO_UTC_OFFSET_SECONDS;
+        // 1970-01-01 was a Thursday
+        ((local_seconds / 86_400 + 4) % 7) as u8
+    }
+
+    // `keywords` are normalized with `normalize_search_text`
+    fn matches_keywords(&self, keywords: &[String]) -> bool {
+        let text = normalize_search_text(&format!(
+            "{} {} {}",
+            self.title,
+            self.description.as_deref().unwrap_or_default(),
+            self.requirements.join(" ")
+        ));
+        keywords.iter().all(|keyword| text.contains(keyword.as_str()))
+    }
+
+    // One page of filtered events in `sort` order, continuing after `cursor`
+    pub fn list(
+        filter: EventFilter,
+        free_days: Option<&[u8]>,
+        viewer: &Viewer,
+        sort: EventSort,
+        cursor: Option<EventCursor>,
+        limit: Option<u32>,
+    ) -> EventPage {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE) as usize;
+
+        let mut events: Vec<(u64, Event)> = Self::filter_events(filter, free_days, viewer)
+            .into_iter()
+            .map(|event| (event.sort_key(&sort), event))
+            .collect();
+        events.sort_by_key(|(sort_key, event)| (*sort_key, event.id));
+
+        let page: Vec<(u64, Event)> = events
+            .into_iter()
+            .skip_while(|(sort_key, event)| match &cursor {
+                Some(cursor) => (*sort_key, event.id) <= (cursor.sort_key, cursor.id),
+                None => false,
+            })
+            .take(limit)
+            .collect();
+
+        let next_cursor = if page.len() == limit {
+            page.last().map(|(sort_key, event)| EventCursor {
+                sort_key: *sort_key,
+                id: event.id,
+            })
+        } else {
+            None
+        };
+
+        EventPage {
+            events: page
+                .into_iter()
+                .map(|(_, event)| EventSummary::from(event))
+                .collect(),
+            next_cursor,
+        }
+    }
+
+    // Ascending key for `sort`, descending orders are stored inverted
+    fn sort_key(&self, sort: &EventSort) -> u64 {
+        match sort {
+            EventSort::EventDate => self.event_date,
+            EventSort::CreatedAt => u64::MAX - self.created_at,
+            EventSort::RemainingCapacity => match self.max_participants {
+                Some(max_participants) => {
+                    let participants = self.participants.len() as u64;
+                    u64::MAX - (max_participants as u64).saturating_sub(participants)
+                }
+                None => 0,
+            },
+            EventSort::Rating => {
+                // Average with 3 decimals, 0 for unrated events
+                let average = RatingSummary::for_event(self.id).average;
+                u64::MAX - (average * 1000.0).round() as u64
+            }
+        }
+    }
+}
+
+// Normalized words of the filter keyword
+pub fn search_keywords(filter: &EventFilter) -> Vec<String> {
+    filter
+        .keyword
+        .as_deref()
+        .map(normalize_search_text)
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
 }
@@ -0,0 +1,229 @@
+use crate::services::event::search_keywords;
+use crate::services::index;
+use crate::services::visibility::Viewer;
+use crate::types::error::Error;
+use crate::types::event::Event;
+use crate::types::filter::EventFilter;
+use crate::types::location::Location;
+use crate::types::response::{EventSummary, NearbyEvent};
+
+const EARTH_RADIUS_KM: f64 = 6_371.0;
+const MAX_RADIUS_KM: f64 = 100.0;
+const GEOHASH_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+// Events are indexed in cells of both precisions, about 39x20 km and 5x5 km,
+// small searches use the fine cells so they load fewer candidates
+const COARSE_PRECISION: usize = 4;
+const FINE_PRECISION: usize = 5;
+const FINE_PRECISION_MAX_RADIUS_KM: f64 = 10.0;
+
+// (governorate id, min latitude, max latitude, min longitude, max longitude).
+// Loose boxes around each governorate of the user canister, they catch
+// coordinates in the wrong governorate, not every border case
+const GOVERNORATE_BOUNDS: [(u8, f64, f64, f64, f64); 26] = [
+    (1, 29.7, 30.4, 31.1, 32.0),  // Cairo
+    (2, 30.5, 31.4, 29.0, 30.2),  // Alexandria
+    (3, 30.9, 31.4, 32.0, 32.6),  // Port Said
+    (4, 25.1, 26.1, 32.2, 33.0),  // Luxor
+    (5, 26.6, 27.9, 30.3, 31.9),  // Asyut
+    (6, 22.0, 25.1, 30.5, 33.9),  // Aswan
+    (7, 30.6, 31.6, 31.1, 32.1),  // Dakahlia
+    (8, 29.9, 31.6, 29.6, 30.9),  // Beheira
+    (9, 30.1, 30.6, 31.0, 31.6),  // Qalyubia
+    (10, 30.1, 31.2, 31.2, 32.2), // Sharqia
+    (11, 27.6, 28.9, 29.9, 31.5), // Minya
+    (12, 30.1, 30.8, 30.3, 31.3), // Monufia
+    (13, 30.9, 31.7, 30.3, 31.4), // Kafr al-Sheikh
+    (14, 28.9, 29.7, 29.9, 31.2), // Fayoum
+    (15, 29.3, 30.3, 31.9, 32.8), // Suez
+    (16, 30.6, 31.2, 30.7, 31.3), // Gharbia
+    (17, 30.2, 31.0, 31.7, 32.8), // Ismailia
+    (18, 28.0, 30.4, 28.5, 31.4), // Giza
+    (19, 27.7, 29.9, 32.6, 34.9), // South Sinai
+    (20, 22.0, 29.5, 32.3, 36.9), // Red Sea
+    (21, 27.5, 31.7, 24.7, 29.5), // Matruh
+    (22, 28.7, 29.4, 30.5, 31.5), // Beni Suef
+    (23, 31.2, 31.6, 31.5, 32.1), // Damietta
+    (24, 22.0, 28.0, 24.6, 31.5), // New Valley
+    (25, 25.7, 26.4, 32.1, 33.1), // Qena
+    (26, 26.1, 26.9, 31.2, 32.3), // Sohag
+];
+
+impl Location {
+    fn coordinates(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    // Coordinates are optional, but must come in pairs and lie in the governorate
+    pub fn validate_coordinates(&self) -> Result<(), Error> {
+        let (latitude, longitude) = match (self.latitude, self.longitude) {
+            (None, None) => return Ok(()),
+            (Some(latitude), Some(longitude)) => (latitude, longitude),
+            _ => {
+                return Err(Error {
+                    code: 400,
+                    error: "Invalid coordinates".to_string(),
+                    message: "Latitude and longitude must be set together".to_string(),
+                })
+            }
+        };
+
+        let in_governorate = GOVERNORATE_BOUNDS
+            .iter()
+            .find(|(id, ..)| *id == self.governorate)
+            .is_some_and(|(_, min_lat, max_lat, min_lon, max_lon)| {
+                (*min_lat..=*max_lat).contains(&latitude)
+                    && (*min_lon..=*max_lon).contains(&longitude)
+            });
+        if !in_governorate {
+            return Err(Error {
+                code: 400,
+                error: "Invalid coordinates".to_string(),
+                message: "The coordinates are outside the selected governorate".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn geohash_terms(&self) -> Vec<String> {
+        match self.coordinates() {
+            Some((latitude, longitude)) => [COARSE_PRECISION, FINE_PRECISION]
+                .iter()
+                .map(|&precision| geohash_term(&geohash(latitude, longitude, precision)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Event {
+    // Events with coordinates within `radius_km`, nearest first
+    pub fn near(
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        filter: EventFilter,
+        free_days: Option<&[u8]>,
+        viewer: &Viewer,
+    ) -> Result<Vec<NearbyEvent>, Error> {
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(Error {
+                code: 400,
+                error: "Invalid coordinates".to_string(),
+                message: "Latitude must be within ±90 and longitude within ±180".to_string(),
+            });
+        }
+        if !(radius_km > 0.0 && radius_km <= MAX_RADIUS_KM) {
+            return Err(Error {
+                code: 400,
+                error: "Invalid radius".to_string(),
+                message: format!("Radius must be between 0 and {} km", MAX_RADIUS_KM),
+            });
+        }
+
+        let keywords = search_keywords(&filter);
+        let cells = covering_cells(latitude, longitude, radius_km);
+        let mut nearby: Vec<(f64, Event)> = index::find_in_cells(&cells, &filter)
+            .into_iter()
+            .filter_map(|event| {
+                let (event_latitude, event_longitude) = event.location.coordinates()?;
+                let distance = haversine_km(latitude, longitude, event_latitude, event_longitude);
+                (distance <= radius_km).then_some((distance, event))
+            })
+            .filter(|(_, event)| {
+                event.is_listed_for(viewer) && event.matches_filter(&filter, &keywords, free_days)
+            })
+            .collect();
+        nearby.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        Ok(nearby
+            .into_iter()
+            .map(|(distance_km, event)| NearbyEvent {
+                event: EventSummary::from(event),
+                distance_km,
+            })
+            .collect())
+    }
+}
+
+fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+fn geohash_term(hash: &str) -> String {
+    format!("geo{}:{}", hash.len(), hash)
+}
+
+fn geohash(latitude: f64, longitude: f64, precision: usize) -> String {
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision);
+    let (mut bits, mut value, mut even) = (0, 0usize, true);
+
+    while hash.len() < precision {
+        // Bits alternate between longitude and latitude, longitude first
+        let (range, coordinate) = if even {
+            (&mut lon_range, longitude)
+        } else {
+            (&mut lat_range, latitude)
+        };
+        let middle = (range.0 + range.1) / 2.0;
+        value <<= 1;
+        if coordinate >= middle {
+            value |= 1;
+            range.0 = middle;
+        } else {
+            range.1 = middle;
+        }
+        even = !even;
+
+        bits += 1;
+        if bits == 5 {
+            hash.push(GEOHASH_ALPHABET[value] as char);
+            bits = 0;
+            value = 0;
+        }
+    }
+    hash
+}
+
+// Index terms of every geohash cell overlapping the bounding box of the circle
+fn covering_cells(latitude: f64, longitude: f64, radius_km: f64) -> Vec<String> {
+    let precision = if radius_km <= FINE_PRECISION_MAX_RADIUS_KM {
+        FINE_PRECISION
+    } else {
+        COARSE_PRECISION
+    };
+    let lon_bits = (precision * 5).div_ceil(2) as i32;
+    let lat_bits = (precision * 5 / 2) as i32;
+    let cell_height = 180.0 / 2f64.powi(lat_bits);
+    let cell_width = 360.0 / 2f64.powi(lon_bits);
+
+    let lat_delta = (radius_km / EARTH_RADIUS_KM).to_degrees();
+    let lon_delta = lat_delta / latitude.to_radians().cos().max(0.01);
+    let min_lat = (latitude - lat_delta).max(-90.0);
+    let max_lat = (latitude + lat_delta).min(90.0);
+    let min_lon = (longitude - lon_delta).max(-180.0);
+    let max_lon = (longitude + lon_delta).min(180.0);
+
+    // Cells are a regular grid starting at (-90, -180), walk their centers
+    let rows = ((min_lat + 90.0) / cell_height).floor() as i64
+        ..=((max_lat + 90.0) / cell_height).floor() as i64;
+    let columns = ((min_lon + 180.0) / cell_width).floor() as i64
+        ..=((max_lon + 180.0) / cell_width).floor() as i64;
+
+    let mut cells = Vec::new();
+    for row in rows {
+        for column in columns.clone() {
+            let cell_latitude = -90.0 + (row as f64 + 0.5) * cell_height;
+            let cell_longitude = -180.0 + (column as f64 + 0.5) * cell_width;
+            let cell = geohash_term(&geohash(cell_latitude, cell_longitude, precision));
+            if !cells.contains(&cell) {
+                cells.push(cell);
+            }
+        }
+    }
+    cells
+}
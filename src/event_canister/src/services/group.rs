@@ -0,0 +1,50 @@
+use crate::services::index;
+use crate::services::visibility::Viewer;
+use crate::types::error::Error;
+use crate::types::event::Event;
+use crate::types::group_admin::GroupPermission;
+use crate::utils::{get_member_group_ids, is_group_admin};
+use candid::Principal;
+
+// Only members can organize events for their group
+pub async fn validate_group(group_id: Option<&String>, creator_id: Principal) -> Result<(), Error> {
+    let Some(group_id) = group_id else {
+        return Ok(());
+    };
+    if !get_member_group_ids(creator_id).await?.contains(group_id) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only members of the group can create events for it".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl Event {
+    // Whether `user_id` can manage the event as an admin of its group holding
+    // ManageEvents. The owners canister is only asked for group events the
+    // user did not create
+    pub async fn is_managed_by_group_admin(
+        event_id: u64,
+        user_id: Principal,
+    ) -> Result<bool, Error> {
+        let Some(event) = Event::get_by_id(event_id) else {
+            return Ok(false);
+        };
+        match event.group_id {
+            Some(group_id) if event.creator_id != user_id => {
+                is_group_admin(&group_id, user_id, GroupPermission::ManageEvents).await
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // Events of a group that are listed for the viewer, ordered by date
+    pub fn get_group_events(group_id: &str, viewer: &Viewer) -> Vec<Event> {
+        index::find_in_group(group_id)
+            .into_iter()
+            .filter(|event| event.is_listed_for(viewer))
+            .collect()
+    }
+}
@@ -0,0 +1,156 @@
+use crate::storage::{EVENTS, EVENT_INDEX};
+use crate::types::event::Event;
+use crate::types::filter::EventFilter;
+use crate::types::response::seconds_to_nanoseconds;
+
+// Every event is indexed under one key per term: (term, event_date, event_id).
+// A term range therefore lists the matching events ordered by date
+const ALL_TERM: &str = "all";
+
+impl Event {
+    fn index_terms(&self) -> Vec<String> {
+        let mut terms = vec![
+            ALL_TERM.to_string(),
+            governorate_term(self.location.governorate),
+            city_term(self.location.governorate, self.location.city),
+            format!("sport:{:?}", self.sport),
+            format!("status:{:?}", self.status),
+        ];
+        terms.extend(self.location.geohash_terms());
+        if let Some(group_id) = &self.group_id {
+            terms.push(group_term(group_id));
+        }
+        terms
+    }
+}
+
+fn governorate_term(governorate: u8) -> String {
+    format!("governorate:{}", governorate)
+}
+
+fn city_term(governorate: u8, city: u16) -> String {
+    format!("city:{}:{}", governorate, city)
+}
+
+fn group_term(group_id: &str) -> String {
+    format!("group:{}", group_id)
+}
+
+// Terms of the filter, most selective first
+fn filter_terms(filter: &EventFilter) -> Vec<String> {
+    let mut terms = Vec::new();
+    match (filter.governorate, filter.city) {
+        (Some(governorate), Some(city)) => terms.push(city_term(governorate, city)),
+        (Some(governorate), None) => terms.push(governorate_term(governorate)),
+        _ => {}
+    }
+    if let Some(sport) = &filter.sport {
+        terms.push(format!("sport:{:?}", sport));
+    }
+    if let Some(status) = &filter.status {
+        terms.push(format!("status:{:?}", status));
+    }
+    if terms.is_empty() {
+        terms.push(ALL_TERM.to_string());
+    }
+    terms
+}
+
+// Keep the index in sync, `before` and `after` are the stored versions of the event
+pub fn reindex(before: Option<&Event>, after: Option<&Event>) {
+    let removed = before.map(|event| (event.event_date, event.id, event.index_terms()));
+    let added = after.map(|event| (event.event_date, event.id, event.index_terms()));
+    if removed == added {
+        return;
+    }
+
+    EVENT_INDEX.with(|index| {
+        let mut index = index.borrow_mut();
+        if let Some((event_date, event_id, terms)) = removed {
+            for term in terms {
+                index.remove(&(term, event_date, event_id));
+            }
+        }
+        if let Some((event_date, event_id, terms)) = added {
+            for term in terms {
+                index.insert((term, event_date, event_id), ());
+            }
+        }
+    });
+}
+
+// Events matching the indexed fields and date range of the filter, ordered
+// by date. The caller still checks the fields that are not indexed
+pub fn find(filter: &EventFilter) -> Vec<Event> {
+    let terms = filter_terms(filter);
+    let (first, others) = terms.split_first().expect("filter_terms is never empty");
+    load(scan(first, others, filter))
+}
+
+// Same for the events in any of the geohash `cells`
+pub fn find_in_cells(cells: &[String], filter: &EventFilter) -> Vec<Event> {
+    let mut terms = filter_terms(filter);
+    terms.retain(|term| term != ALL_TERM);
+
+    // An event is in a single cell per precision, so there are no duplicates
+    let event_ids: Vec<u64> = cells
+        .iter()
+        .flat_map(|cell| scan(cell, &terms, filter))
+        .collect();
+    load(event_ids)
+}
+
+// Events of a social canister group, ordered by date
+pub fn find_in_group(group_id: &str) -> Vec<Event> {
+    let term = group_term(group_id);
+    let event_ids = EVENT_INDEX.with(|index| {
+        index
+            .borrow()
+            .keys_range((term.clone(), 0, 0)..=(term, u64::MAX, u64::MAX))
+            .map(|(_, _, event_id)| event_id)
+            .collect()
+    });
+    load(event_ids)
+}
+
+// IDs under `first` that are also indexed under every term of `others`
+fn scan(first: &str, others: &[String], filter: &EventFilter) -> Vec<u64> {
+    let from = filter.from_date.map_or(0, seconds_to_nanoseconds);
+    let to = filter.to_date.map_or(u64::MAX, seconds_to_nanoseconds);
+    if from > to {
+        return Vec::new();
+    }
+
+    EVENT_INDEX.with(|index| {
+        let index = index.borrow();
+        index
+            .keys_range((first.to_string(), from, 0)..=(first.to_string(), to, u64::MAX))
+            .filter(|(_, event_date, event_id)| {
+                others
+                    .iter()
+                    .all(|term| index.contains_key(&(term.clone(), *event_date, *event_id)))
+            })
+            .map(|(_, _, event_id)| event_id)
+            .collect()
+    })
+}
+
+fn load(event_ids: Vec<u64>) -> Vec<Event> {
+    EVENTS.with(|events| {
+        let events = events.borrow();
+        event_ids
+            .into_iter()
+            .filter_map(|event_id| events.get(&event_id))
+            .collect()
+    })
+}
+
+// Rebuild the index from the stored events
+pub fn backfill() {
+    let events: Vec<Event> = EVENTS.with(|events| events.borrow().values().collect());
+
+    EVENT_INDEX.with(|index| index.borrow_mut().clear_new());
+    for event in &events {
+        reindex(None, Some(event));
+    }
+}
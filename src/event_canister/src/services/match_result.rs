@@ -0,0 +1,329 @@
+use crate::services::scheduler;
+use crate::storage::{MATCH_RESULTS, PLAYER_STATS};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::match_result::{
+    MatchResult, MatchResultInput, MatchResultStatus, ResultDispute, SportStats,
+};
+use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::organizer::OrganizerRight;
+use crate::types::skill::SkillRating;
+use crate::types::sport::Sports;
+use crate::utils::notify_in_background;
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_cdk::futures::spawn;
+
+const NANOS_PER_HOUR: u64 = 60 * 60 * 1_000_000_000;
+// Participants can dispute a result this long after it was submitted
+const DISPUTE_WINDOW: u64 = 48 * NANOS_PER_HOUR;
+const MAX_REASON_LENGTH: usize = 500;
+
+fn event_not_found() -> Error {
+    Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    }
+}
+
+fn invalid_result(message: &str) -> Error {
+    Error {
+        code: 400,
+        error: "Invalid match result".to_string(),
+        message: message.to_string(),
+    }
+}
+
+pub fn sport_key(sport: &Sports) -> String {
+    format!("{:?}", sport)
+}
+
+impl SportStats {
+    fn new(sport: Sports) -> SportStats {
+        SportStats {
+            sport,
+            matches_played: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            goals: 0,
+            assists: 0,
+            mvp_awards: 0,
+        }
+    }
+
+    // Every sport the user has a confirmed match result for
+    pub fn get_for_user(user_id: Principal) -> Vec<SportStats> {
+        PLAYER_STATS.with(|stats| {
+            stats
+                .borrow()
+                .range((user_id, String::new())..)
+                .take_while(|entry| entry.key().0 == user_id)
+                .map(|entry| entry.value())
+                .collect()
+        })
+    }
+}
+
+impl MatchResult {
+    pub fn get(event_id: u64) -> Option<MatchResult> {
+        MATCH_RESULTS.with(|results| results.borrow().get(&event_id))
+    }
+
+    // When the scheduler confirms the result, disputed results wait for a new submission
+    pub fn confirms_at(&self) -> Option<u64> {
+        (self.status == MatchResultStatus::Pending).then_some(self.dispute_until)
+    }
+
+    // Organizers only: replaces a result that is not confirmed yet and
+    // gives participants a new dispute window
+    pub fn submit(
+        event_id: u64,
+        caller: Principal,
+        input: MatchResultInput,
+    ) -> Result<MatchResult, Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if !event.has_right(caller, &OrganizerRight::EditDetails) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only organizers can submit the match result".to_string(),
+            });
+        }
+        if event.status != EventStatus::Completed {
+            return Err(Error {
+                code: 400,
+                error: "Invalid event status".to_string(),
+                message: "Match results can only be submitted for completed events".to_string(),
+            });
+        }
+
+        let previous = Self::get(event_id);
+        if let Some(previous) = &previous {
+            if previous.status == MatchResultStatus::Confirmed {
+                return Err(Error {
+                    code: 409,
+                    error: "Result confirmed".to_string(),
+                    message: "The match result was confirmed and cannot be changed".to_string(),
+                });
+            }
+        }
+        validate_input(&event, &input)?;
+
+        let now = time();
+        let result = MatchResult {
+            event_id,
+            sport: event.sport.clone(),
+            scores: input.scores,
+            players: input.players,
+            status: MatchResultStatus::Pending,
+            disputes: Vec::new(),
+            submitted_by: caller,
+            submitted_at: now,
+            dispute_until: now.saturating_add(DISPUTE_WINDOW),
+        };
+        MATCH_RESULTS.with(|results| results.borrow_mut().insert(event_id, result.clone()));
+        scheduler::reschedule_result(
+            event_id,
+            previous.and_then(|previous| previous.confirms_at()),
+            result.confirms_at(),
+        );
+
+        notify_in_background(
+            event.participants.clone(),
+            NewNotification {
+                content: format!(
+                    "The result of the event {} was posted, you can dispute it for 48 hours",
+                    event.title
+                ),
+                notification_type: NotificationType::Message,
+            },
+        );
+        Ok(result)
+    }
+
+    // Participants only, once per submission and before the window closes
+    pub fn dispute(event_id: u64, user_id: Principal, reason: String) -> Result<(), Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        let mut result = Self::get(event_id).ok_or(Error {
+            code: 404,
+            error: "Match result not found".to_string(),
+            message: "No match result was submitted for the event".to_string(),
+        })?;
+
+        if !event.participants.contains(&user_id) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "User is not a participant of the event".to_string(),
+            });
+        }
+        let now = time();
+        if result.status == MatchResultStatus::Confirmed || now > result.dispute_until {
+            return Err(Error {
+                code: 400,
+                error: "Dispute window closed".to_string(),
+                message: "The match result can no longer be disputed".to_string(),
+            });
+        }
+        if result
+            .disputes
+            .iter()
+            .any(|dispute| dispute.user_id == user_id)
+        {
+            return Err(Error {
+                code: 409,
+                error: "Already disputed".to_string(),
+                message: "User already disputed the match result".to_string(),
+            });
+        }
+
+        let reason = reason.trim();
+        if reason.is_empty() || reason.chars().count() > MAX_REASON_LENGTH {
+            return Err(Error {
+                code: 400,
+                error: "Invalid reason".to_string(),
+                message: format!(
+                    "A reason of at most {} characters is required",
+                    MAX_REASON_LENGTH
+                ),
+            });
+        }
+
+        let confirms_at = result.confirms_at();
+        result.status = MatchResultStatus::Disputed;
+        result.disputes.push(ResultDispute {
+            user_id,
+            reason: reason.to_string(),
+            created_at: now,
+        });
+        scheduler::reschedule_result(event_id, confirms_at, result.confirms_at());
+        MATCH_RESULTS.with(|results| results.borrow_mut().insert(event_id, result));
+
+        notify_in_background(
+            event.organizers_with(&OrganizerRight::EditDetails),
+            NewNotification {
+                content: format!(
+                    "The result of the event {} was disputed: {}",
+                    event.title, reason
+                ),
+                notification_type: NotificationType::Alert,
+            },
+        );
+        Ok(())
+    }
+
+    // Called by the scheduler once the dispute window of an undisputed result
+    // closed, the skill ratings are updated in the background
+    pub fn confirm(event_id: u64) {
+        let Some(mut result) = Self::get(event_id) else {
+            return;
+        };
+        if result.status != MatchResultStatus::Pending || time() < result.dispute_until {
+            return;
+        }
+        let Some(event) = Event::get_by_id(event_id) else {
+            return;
+        };
+
+        result.status = MatchResultStatus::Confirmed;
+        result.record_stats(&event);
+        MATCH_RESULTS.with(|results| results.borrow_mut().insert(event_id, result));
+        spawn(SkillRating::rate_match(event_id));
+    }
+
+    // Every participant played the match, wins and losses are counted for
+    // the ones in a team
+    fn record_stats(&self, event: &Event) {
+        let best = self.scores.iter().map(|score| score.score).max();
+        let leaders = self
+            .scores
+            .iter()
+            .filter(|score| Some(score.score) == best)
+            .count();
+        let teams = event.teams.as_deref().unwrap_or_default();
+
+        PLAYER_STATS.with(|stats| {
+            let mut stats = stats.borrow_mut();
+            for &user_id in &event.participants {
+                let key = (user_id, sport_key(&self.sport));
+                let mut user_stats = stats
+                    .get(&key)
+                    .unwrap_or_else(|| SportStats::new(self.sport.clone()));
+                user_stats.matches_played += 1;
+
+                let team_score = teams
+                    .iter()
+                    .find(|team| team.players.contains(&user_id))
+                    .and_then(|team| self.scores.iter().find(|score| score.team == team.name));
+                match team_score {
+                    Some(score) if Some(score.score) == best && leaders == 1 => {
+                        user_stats.wins += 1
+                    }
+                    Some(score) if Some(score.score) == best => user_stats.draws += 1,
+                    Some(_) => user_stats.losses += 1,
+                    None => {}
+                }
+
+                if let Some(player) = self.players.iter().find(|player| player.user_id == user_id) {
+                    user_stats.goals += player.goals;
+                    user_stats.assists += player.assists;
+                    user_stats.mvp_awards += u32::from(player.mvp);
+                }
+                stats.insert(key, user_stats);
+            }
+        });
+    }
+
+    // Drop the result of a deleted event, confirmed stats are kept
+    pub fn remove(event_id: u64) {
+        if let Some(result) = MATCH_RESULTS.with(|results| results.borrow_mut().remove(&event_id)) {
+            scheduler::reschedule_result(event_id, result.confirms_at(), None);
+        }
+    }
+}
+
+fn validate_input(event: &Event, input: &MatchResultInput) -> Result<(), Error> {
+    if input.scores.len() < 2 {
+        return Err(invalid_result("A score is needed for at least two teams"));
+    }
+    let teams = event.teams.as_deref().unwrap_or_default();
+    for (index, score) in input.scores.iter().enumerate() {
+        if score.team.trim().is_empty() {
+            return Err(invalid_result("Team names cannot be empty"));
+        }
+        if input.scores[..index]
+            .iter()
+            .any(|other| other.team == score.team)
+        {
+            return Err(invalid_result("Every team can only have one score"));
+        }
+        if !teams.is_empty() && !teams.iter().any(|team| team.name == score.team) {
+            return Err(invalid_result(
+                "Scores must be given for the teams of the event",
+            ));
+        }
+    }
+    if !teams.is_empty() && input.scores.len() != teams.len() {
+        return Err(invalid_result(
+            "A score is needed for every team of the event",
+        ));
+    }
+
+    for (index, player) in input.players.iter().enumerate() {
+        if !event.participants.contains(&player.user_id) {
+            return Err(invalid_result("Stats can only be given for participants"));
+        }
+        if input.players[..index]
+            .iter()
+            .any(|other| other.user_id == player.user_id)
+        {
+            return Err(invalid_result("Every player can only have one entry"));
+        }
+    }
+    if input.players.iter().filter(|player| player.mvp).count() > 1 {
+        return Err(invalid_result("At most one player can be the MVP"));
+    }
+    Ok(())
+}
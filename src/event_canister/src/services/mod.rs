@@ -1,2 +1,16 @@
+pub mod approval;
+pub mod attendance;
+pub mod escrow;
 pub mod event;
+pub mod geo;
+pub mod group;
+pub mod index;
+pub mod match_result;
+pub mod organizer;
+pub mod rating;
 pub mod review;
+pub mod scheduler;
+pub mod series;
+pub mod skill;
+pub mod team;
+pub mod visibility;
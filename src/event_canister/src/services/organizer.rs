@@ -0,0 +1,299 @@
+use crate::services::group::validate_group;
+use crate::storage::EVENTS;
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::organizer::{CoOrganizer, OrganizerRight};
+use crate::utils::notify_in_background;
+use candid::Principal;
+use ic_cdk::api::time;
+
+const MAX_CO_ORGANIZERS: usize = 10;
+
+fn event_not_found() -> Error {
+    Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    }
+}
+
+fn save(mut event: Event) {
+    event.updated_at = time();
+    EVENTS.with(|events| events.borrow_mut().insert(event.id, event));
+}
+
+// Organizers only change while the event can still be run
+fn check_not_finished(event: &Event) -> Result<(), Error> {
+    if matches!(
+        event.status,
+        EventStatus::Completed | EventStatus::Cancelled
+    ) {
+        return Err(Error {
+            code: 400,
+            error: "Invalid event status".to_string(),
+            message: "Cannot change the organizers of an event that is completed or cancelled"
+                .to_string(),
+        });
+    }
+    Ok(())
+}
+
+// A creator cannot be a participant of their own event
+fn check_not_joined(event: &Event, user_id: Principal) -> Result<(), Error> {
+    if event.participants.contains(&user_id) || event.waitlist.contains(&user_id) {
+        return Err(Error {
+            code: 409,
+            error: "User already joined".to_string(),
+            message: "The new owner has to leave the event and its waitlist first".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl Event {
+    pub fn co_organizers(&self) -> &[CoOrganizer] {
+        self.co_organizers.as_deref().unwrap_or_default()
+    }
+
+    pub fn is_co_organizer(&self, user_id: Principal) -> bool {
+        self.co_organizers()
+            .iter()
+            .any(|co_organizer| co_organizer.user_id == user_id)
+    }
+
+    // The creator holds every right
+    pub fn has_right(&self, user_id: Principal, right: &OrganizerRight) -> bool {
+        self.creator_id == user_id
+            || self.co_organizers().iter().any(|co_organizer| {
+                co_organizer.user_id == user_id && co_organizer.rights.contains(right)
+            })
+    }
+
+    // The creator and the co-organizers holding `right`
+    pub fn organizers_with(&self, right: &OrganizerRight) -> Vec<Principal> {
+        let mut organizers = vec![self.creator_id];
+        organizers.extend(
+            self.co_organizers()
+                .iter()
+                .filter(|co_organizer| co_organizer.rights.contains(right))
+                .map(|co_organizer| co_organizer.user_id),
+        );
+        organizers
+    }
+
+    // Creator only: adding someone again replaces their rights
+    pub fn add_co_organizer(
+        event_id: u64,
+        caller: Principal,
+        user_id: Principal,
+        rights: Vec<OrganizerRight>,
+    ) -> Result<(), Error> {
+        let mut event = Self::get_owned(event_id, caller)?;
+        check_not_finished(&event)?;
+
+        if user_id == event.creator_id || user_id == Principal::anonymous() {
+            return Err(Error {
+                code: 400,
+                error: "Invalid co-organizer".to_string(),
+                message: "The creator or an anonymous user cannot be a co-organizer".to_string(),
+            });
+        }
+        let mut unique_rights: Vec<OrganizerRight> = Vec::new();
+        for right in rights {
+            if !unique_rights.contains(&right) {
+                unique_rights.push(right);
+            }
+        }
+        let rights = unique_rights;
+        if rights.is_empty() {
+            return Err(Error {
+                code: 400,
+                error: "Invalid rights".to_string(),
+                message: "A co-organizer needs at least one right".to_string(),
+            });
+        }
+
+        let mut co_organizers = event.co_organizers.take().unwrap_or_default();
+        let count = co_organizers.len();
+        let added = match co_organizers.iter_mut().find(|co| co.user_id == user_id) {
+            Some(co_organizer) => {
+                co_organizer.rights = rights;
+                false
+            }
+            None if count >= MAX_CO_ORGANIZERS => {
+                return Err(Error {
+                    code: 400,
+                    error: "Too many co-organizers".to_string(),
+                    message: format!(
+                        "An event can have at most {} co-organizers",
+                        MAX_CO_ORGANIZERS
+                    ),
+                });
+            }
+            None => {
+                co_organizers.push(CoOrganizer { user_id, rights });
+                true
+            }
+        };
+        event.co_organizers = Some(co_organizers);
+
+        if added {
+            notify_in_background(
+                vec![user_id],
+                NewNotification {
+                    content: format!("You are now a co-organizer of the event: {}", event.title),
+                    notification_type: NotificationType::Message,
+                },
+            );
+        }
+        save(event);
+        Ok(())
+    }
+
+    // The creator removes a co-organizer, or a co-organizer steps down
+    pub fn remove_co_organizer(
+        event_id: u64,
+        caller: Principal,
+        user_id: Principal,
+    ) -> Result<(), Error> {
+        let mut event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if event.creator_id != caller && user_id != caller {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only creator can remove co-organizers".to_string(),
+            });
+        }
+        if !event.is_co_organizer(user_id) {
+            return Err(Error {
+                code: 404,
+                error: "Co-organizer not found".to_string(),
+                message: "The user is not a co-organizer of the event".to_string(),
+            });
+        }
+
+        if let Some(co_organizers) = event.co_organizers.as_mut() {
+            co_organizers.retain(|co_organizer| co_organizer.user_id != user_id);
+        }
+        save(event);
+        Ok(())
+    }
+
+    // Creator only: first step of handing the event over, `new_owner` has to accept.
+    // Asking someone else replaces the pending request
+    pub fn transfer_ownership(
+        event_id: u64,
+        caller: Principal,
+        new_owner: Principal,
+    ) -> Result<(), Error> {
+        let mut event = Self::get_owned(event_id, caller)?;
+        check_not_finished(&event)?;
+
+        if new_owner == caller || new_owner == Principal::anonymous() {
+            return Err(Error {
+                code: 400,
+                error: "Invalid new owner".to_string(),
+                message: "The event can only be transferred to another user".to_string(),
+            });
+        }
+        // The series creator keeps managing every occurrence
+        if event.series_id.is_some() {
+            return Err(Error {
+                code: 400,
+                error: "Event is part of a series".to_string(),
+                message: "Occurrences of a series cannot be transferred".to_string(),
+            });
+        }
+        check_not_joined(&event, new_owner)?;
+
+        event.pending_owner = Some(new_owner);
+        notify_in_background(
+            vec![new_owner],
+            NewNotification {
+                content: format!("You were asked to take over the event: {}", event.title),
+                notification_type: NotificationType::Message,
+            },
+        );
+        save(event);
+        Ok(())
+    }
+
+    // Second step, the caller becomes the creator. Group events stay with
+    // members of the group
+    pub async fn accept_ownership(event_id: u64, caller: Principal) -> Result<(), Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if event.pending_owner != Some(caller) {
+            return Err(Error {
+                code: 404,
+                error: "Transfer not found".to_string(),
+                message: "No pending ownership transfer to the caller".to_string(),
+            });
+        }
+        validate_group(event.group_id.as_ref(), caller).await?;
+
+        // The event may have changed while the social canister was asked
+        let mut event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if event.pending_owner != Some(caller) {
+            return Err(Error {
+                code: 409,
+                error: "Transfer cancelled".to_string(),
+                message: "The ownership transfer was cancelled".to_string(),
+            });
+        }
+        check_not_finished(&event)?;
+        check_not_joined(&event, caller)?;
+
+        let previous_owner = event.creator_id;
+        event.creator_id = caller;
+        event.pending_owner = None;
+        if let Some(co_organizers) = event.co_organizers.as_mut() {
+            co_organizers.retain(|co_organizer| co_organizer.user_id != caller);
+        }
+
+        notify_in_background(
+            vec![previous_owner],
+            NewNotification {
+                content: format!("The event {} has a new owner", event.title),
+                notification_type: NotificationType::Message,
+            },
+        );
+        save(event);
+        Ok(())
+    }
+
+    // The creator withdraws the transfer, or the new owner declines it
+    pub fn cancel_ownership_transfer(event_id: u64, caller: Principal) -> Result<(), Error> {
+        let mut event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        let Some(pending_owner) = event.pending_owner else {
+            return Err(Error {
+                code: 404,
+                error: "Transfer not found".to_string(),
+                message: "The event has no pending ownership transfer".to_string(),
+            });
+        };
+        if event.creator_id != caller && pending_owner != caller {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only creator or the new owner can cancel the transfer".to_string(),
+            });
+        }
+
+        event.pending_owner = None;
+        save(event);
+        Ok(())
+    }
+
+    fn get_owned(event_id: u64, caller: Principal) -> Result<Event, Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if event.creator_id != caller {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only creator can manage the organizers".to_string(),
+            });
+        }
+        Ok(event)
+    }
+}
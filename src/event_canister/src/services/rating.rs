@@ -0,0 +1,103 @@
+use crate::storage::{EVENT_RATINGS, ORGANIZER_RATINGS, REVIEWS};
+use crate::types::event::Event;
+use crate::types::rating::RatingSummary;
+use candid::Principal;
+
+impl RatingSummary {
+    pub fn for_event(event_id: u64) -> RatingSummary {
+        EVENT_RATINGS.with(|ratings| ratings.borrow().get(&event_id).unwrap_or_default())
+    }
+
+    pub fn for_organizer(organizer: Principal) -> RatingSummary {
+        ORGANIZER_RATINGS.with(|ratings| ratings.borrow().get(&organizer).unwrap_or_default())
+    }
+
+    // Apply a review change to the event and organizer aggregates,
+    // `removed`/`added` are the old and new rating (None when created/deleted)
+    pub fn record(event_id: u64, removed: Option<u8>, added: Option<u8>) {
+        // Reviews of deleted events no longer count
+        let Some(event) = Event::get_by_id(event_id) else {
+            return;
+        };
+
+        EVENT_RATINGS.with(|ratings| {
+            let mut ratings = ratings.borrow_mut();
+            let mut summary = ratings.get(&event_id).unwrap_or_default();
+            summary.apply(removed, added);
+            ratings.insert(event_id, summary);
+        });
+
+        ORGANIZER_RATINGS.with(|ratings| {
+            let mut ratings = ratings.borrow_mut();
+            let mut summary = ratings.get(&event.creator_id).unwrap_or_default();
+            summary.apply(removed, added);
+            ratings.insert(event.creator_id, summary);
+        });
+    }
+
+    fn apply(&mut self, removed: Option<u8>, added: Option<u8>) {
+        if self.histogram.len() < 5 {
+            self.histogram.resize(5, 0);
+        }
+
+        if let Some(rating) = removed {
+            self.count = self.count.saturating_sub(1);
+            self.sum = self.sum.saturating_sub(rating as u64);
+            let bucket = &mut self.histogram[rating as usize - 1];
+            *bucket = bucket.saturating_sub(1);
+        }
+        if let Some(rating) = added {
+            self.count += 1;
+            self.sum += rating as u64;
+            self.histogram[rating as usize - 1] += 1;
+        }
+        self.update_average();
+    }
+
+    fn update_average(&mut self) {
+        self.average = if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        };
+    }
+
+    // Drop the aggregate of a deleted event and take its reviews out of the
+    // organizer's one, the reviews themselves are kept but no longer count
+    pub fn remove_event(event_id: u64, organizer: Principal) {
+        let Some(removed) = EVENT_RATINGS.with(|ratings| ratings.borrow_mut().remove(&event_id))
+        else {
+            return;
+        };
+
+        ORGANIZER_RATINGS.with(|ratings| {
+            let mut ratings = ratings.borrow_mut();
+            let mut summary = ratings.get(&organizer).unwrap_or_default();
+            summary.count = summary.count.saturating_sub(removed.count);
+            summary.sum = summary.sum.saturating_sub(removed.sum);
+            for (bucket, count) in summary.histogram.iter_mut().zip(&removed.histogram) {
+                *bucket = bucket.saturating_sub(*count);
+            }
+            summary.update_average();
+            ratings.insert(organizer, summary);
+        });
+    }
+
+    // Rebuild every aggregate from the stored reviews, the ones of deleted
+    // events are skipped by `record`
+    pub fn backfill() {
+        EVENT_RATINGS.with(|ratings| ratings.borrow_mut().clear_new());
+        ORGANIZER_RATINGS.with(|ratings| ratings.borrow_mut().clear_new());
+
+        let reviews: Vec<(u64, u8)> = REVIEWS.with(|reviews| {
+            reviews
+                .borrow()
+                .values()
+                .map(|review| (review.event_id, review.rating))
+                .collect()
+        });
+        for (event_id, rating) in reviews {
+            Self::record(event_id, None, Some(rating));
+        }
+    }
+}
@@ -1,7 +1,10 @@
-use crate::storage::{ReviewIds, EVENTS, EVENT_REVIEWS, REVIEWS};
+use crate::storage::{ReviewIds, EVENT_REVIEWS, REVIEWS, REVIEWS_BY_USER};
 use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::rating::RatingSummary;
 use crate::types::review::Review;
 use crate::utils::generate_unique_id;
+use candid::Principal;
 use ic_cdk::api::time;
 
 impl Review {
@@ -9,34 +12,34 @@ impl Review {
         let caller = ic_cdk::api::msg_caller();
 
         // Validate event exists
-        let event_exists = EVENTS.with(|events| events.borrow().contains_key(&event_id));
+        let event = Event::get_by_id(event_id).ok_or(Error {
+            code: 404,
+            error: "Event not found".to_string(),
+            message: "No event found with the given ID".to_string(),
+        })?;
 
-        if !event_exists {
+        // Only participants can review, once the event is over
+        if !event.participants.contains(&caller) {
             return Err(Error {
-                code: 404,
-                error: "Event not found".to_string(),
-                message: "No event found with the given ID".to_string(),
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only participants can review an event".to_string(),
             });
         }
 
-        // Validate rating
-        if rating == 0 || rating > 5 {
+        if event.status != EventStatus::Completed {
             return Err(Error {
                 code: 400,
-                error: "Invalid rating".to_string(),
-                message: "Rating must be between 1 and 5".to_string(),
+                error: "Invalid event status".to_string(),
+                message: "Only completed events can be reviewed".to_string(),
             });
         }
 
-        // Check if user already reviewed this event
-        let mut already_reviewed = false;
+        Self::validate_rating(rating)?;
 
-        REVIEWS.with(|reviews| {
-            already_reviewed = reviews
-                .borrow()
-                .values()
-                .any(|r| r.event_id == event_id && r.user_id == caller);
-        });
+        // Check if user already reviewed this event
+        let already_reviewed = REVIEWS_BY_USER
+            .with(|reviews_by_user| reviews_by_user.borrow().contains_key(&(event_id, caller)));
 
         if already_reviewed {
             return Err(Error {
@@ -55,6 +58,7 @@ impl Review {
             rating,
             comment,
             created_at: time(),
+            updated_at: None,
         };
 
         // Store the review
@@ -62,6 +66,15 @@ impl Review {
             reviews.borrow_mut().insert(review_id, new_review);
         });
 
+        // Update the (event, user) -> review index
+        REVIEWS_BY_USER.with(|reviews_by_user| {
+            reviews_by_user
+                .borrow_mut()
+                .insert((event_id, caller), review_id);
+        });
+
+        RatingSummary::record(event_id, None, Some(rating));
+
         // Update the event -> reviews index
         EVENT_REVIEWS.with(|event_reviews| {
             let mut borrowed = event_reviews.borrow_mut();
@@ -98,4 +111,90 @@ impl Review {
     pub fn get_by_id(review_id: u64) -> Option<Review> {
         REVIEWS.with(|reviews| reviews.borrow().get(&review_id))
     }
+
+    pub fn update(review_id: u64, rating: u8, comment: Option<String>) -> Result<(), Error> {
+        let mut review = Self::get_own(review_id)?;
+        Self::validate_rating(rating)?;
+
+        RatingSummary::record(review.event_id, Some(review.rating), Some(rating));
+        review.rating = rating;
+        review.comment = comment;
+        review.updated_at = Some(time());
+
+        REVIEWS.with(|reviews| {
+            reviews.borrow_mut().insert(review_id, review);
+        });
+        Ok(())
+    }
+
+    pub fn delete(review_id: u64) -> Result<(), Error> {
+        let review = Self::get_own(review_id)?;
+        RatingSummary::record(review.event_id, Some(review.rating), None);
+
+        REVIEWS.with(|reviews| {
+            reviews.borrow_mut().remove(&review_id);
+        });
+
+        REVIEWS_BY_USER.with(|reviews_by_user| {
+            reviews_by_user
+                .borrow_mut()
+                .remove(&(review.event_id, review.user_id));
+        });
+
+        EVENT_REVIEWS.with(|event_reviews| {
+            let mut borrowed = event_reviews.borrow_mut();
+            if let Some(mut review_ids) = borrowed.get(&review.event_id) {
+                review_ids.0.retain(|&id| id != review_id);
+                borrowed.insert(review.event_id, review_ids);
+            }
+        });
+        Ok(())
+    }
+
+    // Get a review written by the caller
+    fn get_own(review_id: u64) -> Result<Review, Error> {
+        let review = Self::get_by_id(review_id).ok_or(Error {
+            code: 404,
+            error: "Review not found".to_string(),
+            message: "No review found with the given ID".to_string(),
+        })?;
+
+        if review.user_id != ic_cdk::api::msg_caller() {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only the author can change a review".to_string(),
+            });
+        }
+        Ok(review)
+    }
+
+    fn validate_rating(rating: u8) -> Result<(), Error> {
+        if rating == 0 || rating > 5 {
+            return Err(Error {
+                code: 400,
+                error: "Invalid rating".to_string(),
+                message: "Rating must be between 1 and 5".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    // Rebuild the (event, user) -> review index from the stored reviews
+    pub fn backfill_user_index() {
+        let entries: Vec<((u64, Principal), u64)> = REVIEWS.with(|reviews| {
+            reviews
+                .borrow()
+                .values()
+                .map(|review| ((review.event_id, review.user_id), review.id))
+                .collect()
+        });
+
+        REVIEWS_BY_USER.with(|reviews_by_user| {
+            let mut reviews_by_user = reviews_by_user.borrow_mut();
+            for (key, review_id) in entries {
+                reviews_by_user.insert(key, review_id);
+            }
+        });
+    }
 }
@@ -0,0 +1,384 @@
+use crate::services::{escrow, index};
+use crate::storage::{
+    ReminderOffsets, EVENTS, REMINDER_OFFSETS, REMINDER_SCHEDULE, RESULT_SCHEDULE, SERIES_SCHEDULE,
+    STATUS_SCHEDULE,
+};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::match_result::MatchResult;
+use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::response::seconds_to_nanoseconds;
+use crate::types::series::EventSeries;
+use crate::utils::notify_in_background;
+use ic_cdk::api::time;
+use ic_cdk::futures::spawn;
+use ic_cdk_timers::TimerId;
+use std::cell::RefCell;
+use std::time::Duration;
+
+const NANOS_PER_HOUR: u64 = 60 * 60 * 1_000_000_000;
+const MAX_REMINDERS: usize = 5;
+
+// Timers do not survive upgrades, only the schedules in stable memory do.
+// This is the (wake up time, timer) currently armed for their earliest entry
+thread_local! {
+    static ARMED_TIMER: RefCell<Option<(u64, TimerId)>> = const { RefCell::new(None) };
+}
+
+impl Event {
+    pub fn ends_at(&self) -> u64 {
+        self.event_date
+            .saturating_add(self.duration_hours as u64 * NANOS_PER_HOUR)
+    }
+
+    // When the scheduler has to look at this event next, if ever
+    pub fn next_transition_at(&self) -> Option<u64> {
+        match self.status {
+            EventStatus::Upcoming => Some(self.event_date),
+            EventStatus::InProgress => Some(self.ends_at()),
+            EventStatus::Completed | EventStatus::Cancelled => None,
+        }
+    }
+
+    // Reminders are only sent for upcoming events, relative to their date
+    pub fn reminders_anchor(&self) -> Option<u64> {
+        (self.status == EventStatus::Upcoming).then_some(self.event_date)
+    }
+
+    // Apply every transition that is due at `now`, returns whether the status changed
+    fn advance_status(&mut self, now: u64) -> bool {
+        let status = match self.status {
+            EventStatus::Upcoming | EventStatus::InProgress if now >= self.ends_at() => {
+                EventStatus::Completed
+            }
+            EventStatus::Upcoming if now >= self.event_date => EventStatus::InProgress,
+            _ => return false,
+        };
+        self.status = status;
+        self.updated_at = now;
+        true
+    }
+}
+
+// Keep the status schedule in sync after an event was created, updated or deleted
+pub fn reschedule(event_id: u64, before: Option<u64>, after: Option<u64>) {
+    if before == after {
+        return;
+    }
+
+    STATUS_SCHEDULE.with(|schedule| {
+        let mut schedule = schedule.borrow_mut();
+        if let Some(due) = before {
+            schedule.remove(&(due, event_id));
+        }
+        if let Some(due) = after {
+            schedule.insert((due, event_id), ());
+        }
+    });
+    arm();
+}
+
+// Same for the reminders, `before` and `after` are `Event::reminders_anchor`
+pub fn reschedule_reminders(event_id: u64, before: Option<u64>, after: Option<u64>) {
+    if before == after {
+        return;
+    }
+
+    let offsets = get_reminder_offsets();
+    let now = time();
+    REMINDER_SCHEDULE.with(|schedule| {
+        let mut schedule = schedule.borrow_mut();
+        // All the old keys go first, a new reminder can be due when an old
+        // one of another offset was (e.g. the date moved by the difference
+        // between two offsets)
+        if let Some(event_date) = before {
+            for &offset in &offsets {
+                let due = event_date.saturating_sub(seconds_to_nanoseconds(offset));
+                schedule.remove(&(due, event_id));
+            }
+        }
+        if let Some(event_date) = after {
+            for &offset in &offsets {
+                // Reminders that are already due are skipped
+                let due = event_date.saturating_sub(seconds_to_nanoseconds(offset));
+                if due > now {
+                    schedule.insert((due, event_id), offset);
+                }
+            }
+        }
+    });
+    arm();
+}
+
+// Same for the series, `before` and `after` are `EventSeries::next_materialization_at`
+pub fn reschedule_series(series_id: u64, before: Option<u64>, after: Option<u64>) {
+    if before == after {
+        return;
+    }
+
+    SERIES_SCHEDULE.with(|schedule| {
+        let mut schedule = schedule.borrow_mut();
+        if let Some(due) = before {
+            schedule.remove(&(due, series_id));
+        }
+        if let Some(due) = after {
+            schedule.insert((due, series_id), ());
+        }
+    });
+    arm();
+}
+
+// Same for the match results, `before` and `after` are `MatchResult::confirms_at`
+pub fn reschedule_result(event_id: u64, before: Option<u64>, after: Option<u64>) {
+    if before == after {
+        return;
+    }
+
+    RESULT_SCHEDULE.with(|schedule| {
+        let mut schedule = schedule.borrow_mut();
+        if let Some(due) = before {
+            schedule.remove(&(due, event_id));
+        }
+        if let Some(due) = after {
+            schedule.insert((due, event_id), ());
+        }
+    });
+    arm();
+}
+
+pub fn get_reminder_offsets() -> Vec<u64> {
+    REMINDER_OFFSETS.with(|offsets| offsets.borrow().get().0.clone())
+}
+
+// Offsets are in seconds before the event date, existing reminders are rebuilt
+pub fn set_reminder_offsets(mut offsets: Vec<u64>) -> Result<(), Error> {
+    offsets.sort_unstable_by(|a, b| b.cmp(a));
+    offsets.dedup();
+
+    if offsets.len() > MAX_REMINDERS {
+        return Err(Error {
+            code: 400,
+            error: "Too many reminders".to_string(),
+            message: format!("At most {} reminders can be configured", MAX_REMINDERS),
+        });
+    }
+    if offsets.contains(&0) {
+        return Err(Error {
+            code: 400,
+            error: "Invalid reminder".to_string(),
+            message: "Reminders must be sent before the event starts".to_string(),
+        });
+    }
+
+    REMINDER_OFFSETS.with(|cell| {
+        cell.borrow_mut().set(ReminderOffsets(offsets));
+    });
+    backfill_reminders();
+    Ok(())
+}
+
+// Rebuild the status schedule from the stored events
+pub fn backfill() {
+    let entries: Vec<(u64, u64)> = EVENTS.with(|events| {
+        events
+            .borrow()
+            .iter()
+            .filter_map(|entry| {
+                let event = entry.value();
+                event.next_transition_at().map(|due| (due, event.id))
+            })
+            .collect()
+    });
+
+    STATUS_SCHEDULE.with(|schedule| {
+        let mut schedule = schedule.borrow_mut();
+        schedule.clear_new();
+        for key in entries {
+            schedule.insert(key, ());
+        }
+    });
+}
+
+// Rebuild the reminder schedule from the stored events
+pub fn backfill_reminders() {
+    let anchors: Vec<(u64, u64)> = EVENTS.with(|events| {
+        events
+            .borrow()
+            .iter()
+            .filter_map(|entry| {
+                let event = entry.value();
+                event
+                    .reminders_anchor()
+                    .map(|event_date| (event.id, event_date))
+            })
+            .collect()
+    });
+
+    REMINDER_SCHEDULE.with(|schedule| schedule.borrow_mut().clear_new());
+    for (event_id, event_date) in anchors {
+        reschedule_reminders(event_id, None, Some(event_date));
+    }
+}
+
+// Called after an upgrade, overdue entries run right away
+pub fn resume() {
+    arm();
+}
+
+// Make sure a timer is armed for the earliest scheduled entry
+fn arm() {
+    let next_transition = STATUS_SCHEDULE.with(|schedule| schedule.borrow().first_key_value());
+    let next_reminder = REMINDER_SCHEDULE.with(|schedule| schedule.borrow().first_key_value());
+    let next_series = SERIES_SCHEDULE.with(|schedule| schedule.borrow().first_key_value());
+    let next_result = RESULT_SCHEDULE.with(|schedule| schedule.borrow().first_key_value());
+    let Some(due) = [
+        next_transition.map(|((due, _), _)| due),
+        next_reminder.map(|((due, _), _)| due),
+        next_series.map(|((due, _), _)| due),
+        next_result.map(|((due, _), _)| due),
+    ]
+    .into_iter()
+    .flatten()
+    .min() else {
+        return;
+    };
+
+    ARMED_TIMER.with(|armed| {
+        let mut armed = armed.borrow_mut();
+        if let Some((wake_up, timer_id)) = *armed {
+            if wake_up <= due {
+                return;
+            }
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+
+        let delay = Duration::from_nanos(due.saturating_sub(time()));
+        let timer_id = ic_cdk_timers::set_timer(delay, run_due);
+        *armed = Some((due, timer_id));
+    });
+}
+
+fn run_due() {
+    ARMED_TIMER.with(|armed| *armed.borrow_mut() = None);
+
+    let now = time();
+    run_due_transitions(now);
+    send_due_reminders(now);
+    materialize_due_series(now);
+    confirm_due_results(now);
+    arm();
+}
+
+fn run_due_transitions(now: u64) {
+    let due: Vec<(u64, u64)> = STATUS_SCHEDULE.with(|schedule| {
+        schedule
+            .borrow()
+            .keys_range(..(now.saturating_add(1), 0))
+            .collect()
+    });
+
+    for (due_at, event_id) in due {
+        STATUS_SCHEDULE.with(|schedule| schedule.borrow_mut().remove(&(due_at, event_id)));
+
+        let next = EVENTS.with(|events| {
+            let mut events = events.borrow_mut();
+            let mut event = events.get(&event_id)?;
+            let before = event.clone();
+            if event.advance_status(now) {
+                index::reindex(Some(&before), Some(&event));
+                events.insert(event_id, event.clone());
+                // Fees go to the creator and attendance is counted once the event took place
+                if event.status == EventStatus::Completed {
+                    escrow::settle_in_background(event_id);
+                    event.record_attendance();
+                    Event::remove_teammate_requests(event_id);
+                }
+            }
+            event.next_transition_at()
+        });
+
+        if let Some(next) = next {
+            STATUS_SCHEDULE.with(|schedule| schedule.borrow_mut().insert((next, event_id), ()));
+        }
+    }
+}
+
+fn send_due_reminders(now: u64) {
+    let due: Vec<((u64, u64), u64)> = REMINDER_SCHEDULE.with(|schedule| {
+        schedule
+            .borrow()
+            .range(..(now.saturating_add(1), 0))
+            .map(|entry| (*entry.key(), entry.value()))
+            .collect()
+    });
+
+    for ((due_at, event_id), offset) in due {
+        REMINDER_SCHEDULE.with(|schedule| schedule.borrow_mut().remove(&(due_at, event_id)));
+
+        // Entries left behind by a date change or cancellation are dropped here
+        let Some(event) = Event::get_by_id(event_id) else {
+            continue;
+        };
+        if event.reminders_anchor() != Some(due_at.saturating_add(seconds_to_nanoseconds(offset))) {
+            continue;
+        }
+
+        notify_in_background(
+            event.participants,
+            NewNotification {
+                content: format!(
+                    "Reminder: {} starts in {}",
+                    event.title,
+                    format_offset(offset)
+                ),
+                notification_type: NotificationType::Reminder,
+            },
+        );
+    }
+}
+
+// Creating occurrences needs random IDs, so it runs in the background
+// and reschedules the series once done
+fn materialize_due_series(now: u64) {
+    let due: Vec<(u64, u64)> = SERIES_SCHEDULE.with(|schedule| {
+        schedule
+            .borrow()
+            .keys_range(..(now.saturating_add(1), 0))
+            .collect()
+    });
+
+    for (due_at, series_id) in due {
+        SERIES_SCHEDULE.with(|schedule| schedule.borrow_mut().remove(&(due_at, series_id)));
+        spawn(EventSeries::materialize(series_id));
+    }
+}
+
+fn confirm_due_results(now: u64) {
+    let due: Vec<(u64, u64)> = RESULT_SCHEDULE.with(|schedule| {
+        schedule
+            .borrow()
+            .keys_range(..(now.saturating_add(1), 0))
+            .collect()
+    });
+
+    for (due_at, event_id) in due {
+        RESULT_SCHEDULE.with(|schedule| schedule.borrow_mut().remove(&(due_at, event_id)));
+        MatchResult::confirm(event_id);
+    }
+}
+
+fn format_offset(seconds: u64) -> String {
+    match seconds {
+        s if s % 86_400 == 0 => plural(s / 86_400, "day"),
+        s if s % 3_600 == 0 => plural(s / 3_600, "hour"),
+        s => plural(s.div_ceil(60), "minute"),
+    }
+}
+
+fn plural(count: u64, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
@@ -0,0 +1,387 @@
+use crate::services::event::CAIRO_UTC_OFFSET_SECONDS;
+use crate::services::scheduler;
+use crate::services::visibility::Viewer;
+use crate::storage::{EVENT_SERIES, SERIES_EVENTS};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus, EventUpdate};
+use crate::types::response::{nanoseconds_to_seconds, seconds_to_nanoseconds};
+use crate::types::series::{
+    CreateEventSeriesInput, EventSeries, RecurrenceEnd, RecurrenceFrequency, RecurrenceRule,
+    SeriesScope,
+};
+use crate::utils::generate_unique_id;
+use ic_cdk::api::time;
+
+const NANOS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+// Occurrences are created this long before their date
+const MATERIALIZE_WINDOW: u64 = 28 * NANOS_PER_DAY;
+const MAX_OCCURRENCES: u32 = 104;
+
+impl EventSeries {
+    pub async fn new(input: CreateEventSeriesInput) -> Result<EventSeries, Error> {
+        let caller = ic_cdk::api::msg_caller();
+        Event::validate_input(&input.event).await?;
+        validate_rule(&input.rule, seconds_to_nanoseconds(input.event.event_date))?;
+
+        let now = time();
+        let series = EventSeries {
+            id: generate_unique_id().await,
+            creator_id: caller,
+            template: input.event,
+            rule: input.rule,
+            materialized: 0,
+            created_at: now,
+            updated_at: now,
+        };
+        series.save();
+
+        Self::materialize(series.id).await;
+        Ok(Self::get_by_id(series.id).unwrap_or(series))
+    }
+
+    pub fn get_by_id(series_id: u64) -> Option<EventSeries> {
+        EVENT_SERIES.with(|series| series.borrow().get(&series_id))
+    }
+
+    // The creator and whoever can open one of the occurrences
+    pub fn is_visible_to(&self, viewer: &Viewer) -> bool {
+        self.creator_id == viewer.id
+            || Self::get_events(self.id)
+                .iter()
+                .any(|event| event.is_visible_to(viewer))
+    }
+
+    fn save(&self) {
+        EVENT_SERIES.with(|series| {
+            series.borrow_mut().insert(self.id, self.clone());
+        });
+    }
+
+    // Date of occurrence `index` in nanoseconds, None past the end of the series
+    pub fn occurrence_date(&self, index: u32) -> Option<u64> {
+        if index >= MAX_OCCURRENCES {
+            return None;
+        }
+        let first = seconds_to_nanoseconds(self.template.event_date);
+        let date = nth_date(&self.rule.frequency, first, index);
+        match self.rule.end {
+            RecurrenceEnd::Count(count) if index >= count => None,
+            RecurrenceEnd::Until(until) if date > seconds_to_nanoseconds(until) => None,
+            _ => Some(date),
+        }
+    }
+
+    // When the next occurrence has to be created, if ever
+    pub fn next_materialization_at(&self) -> Option<u64> {
+        self.occurrence_date(self.materialized)
+            .map(|event_date| event_date.saturating_sub(MATERIALIZE_WINDOW))
+    }
+
+    // Create the occurrences that entered the window, the first one right away
+    pub async fn materialize(series_id: u64) {
+        loop {
+            let Some(mut series) = Self::get_by_id(series_id) else {
+                return;
+            };
+            let index = series.materialized;
+            let Some(event_date) = series.occurrence_date(index) else {
+                return;
+            };
+
+            let now = time();
+            if index > 0 && event_date > now.saturating_add(MATERIALIZE_WINDOW) {
+                scheduler::reschedule_series(series_id, None, series.next_materialization_at());
+                return;
+            }
+
+            // Claim the occurrence before awaiting, so it is never created twice
+            series.materialized += 1;
+            series.save();
+
+            // Occurrences missed while the canister was stopped are skipped
+            if event_date <= now {
+                continue;
+            }
+
+            let mut input = series.template.clone();
+            input.event_date = nanoseconds_to_seconds(event_date);
+            let event = Event::create(series.creator_id, input, event_date, Some(series_id)).await;
+            SERIES_EVENTS.with(|series_events| {
+                series_events
+                    .borrow_mut()
+                    .insert((series_id, index), event.id);
+            });
+        }
+    }
+
+    // Created occurrences, oldest first
+    pub fn get_events(series_id: u64) -> Vec<Event> {
+        Self::get_occurrences(series_id, 0)
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    fn get_occurrences(series_id: u64, from: u32) -> Vec<(u32, Event)> {
+        let event_ids: Vec<(u32, u64)> = SERIES_EVENTS.with(|series_events| {
+            series_events
+                .borrow()
+                .range((series_id, from)..=(series_id, u32::MAX))
+                .map(|entry| (entry.key().1, entry.value()))
+                .collect()
+        });
+
+        event_ids
+            .into_iter()
+            .filter_map(|(index, event_id)| Event::get_by_id(event_id).map(|event| (index, event)))
+            .collect()
+    }
+
+    pub fn update_occurrences(
+        event_id: u64,
+        update: EventUpdate,
+        scope: SeriesScope,
+    ) -> Result<(), Error> {
+        if scope == SeriesScope::ThisOccurrence {
+            return Event::update(event_id, update, false);
+        }
+
+        let (mut series, index) = Self::find_occurrence(event_id)?;
+        if update.event_date.is_some() || update.status.is_some() {
+            return Err(Error {
+                code: 400,
+                error: "Invalid series update".to_string(),
+                message: "Dates and statuses can only be changed for a single occurrence"
+                    .to_string(),
+            });
+        }
+
+        // Nothing is changed unless every occurrence accepts the update
+        series.apply_update(&update)?;
+        let occurrences = Self::get_unfinished_occurrences(series.id, index);
+        for (_, event) in &occurrences {
+            Event::validate_update(event.id, &update, false)?;
+        }
+        series.updated_at = time();
+        series.save();
+
+        for (_, event) in occurrences {
+            Event::update(event.id, update.clone(), false)?;
+        }
+        Ok(())
+    }
+
+    pub fn cancel_occurrences(event_id: u64, scope: SeriesScope) -> Result<(), Error> {
+        let cancel = EventUpdate {
+            status: Some(EventStatus::Cancelled),
+            ..Default::default()
+        };
+        if scope == SeriesScope::ThisOccurrence {
+            return Event::update(event_id, cancel, false);
+        }
+
+        // The series ends before this occurrence, no new ones are created
+        let (mut series, index) = Self::find_occurrence(event_id)?;
+        let occurrences = Self::get_unfinished_occurrences(series.id, index);
+        for (_, event) in &occurrences {
+            Event::validate_update(event.id, &cancel, false)?;
+        }
+        let scheduled_at = series.next_materialization_at();
+        series.rule.end = RecurrenceEnd::Count(index);
+        series.updated_at = time();
+        series.save();
+        scheduler::reschedule_series(series.id, scheduled_at, series.next_materialization_at());
+
+        for (_, event) in occurrences {
+            Event::update(event.id, cancel.clone(), false)?;
+        }
+        Ok(())
+    }
+
+    fn get_unfinished_occurrences(series_id: u64, from: u32) -> Vec<(u32, Event)> {
+        Self::get_occurrences(series_id, from)
+            .into_iter()
+            .filter(|(_, event)| {
+                !matches!(
+                    event.status,
+                    EventStatus::Completed | EventStatus::Cancelled
+                )
+            })
+            .collect()
+    }
+
+    // The series of an occurrence owned by the caller, and the occurrence number
+    fn find_occurrence(event_id: u64) -> Result<(EventSeries, u32), Error> {
+        let not_found = Error {
+            code: 404,
+            error: "Series not found".to_string(),
+            message: "The event is not an occurrence of a series".to_string(),
+        };
+        let series = Event::get_by_id(event_id)
+            .and_then(|event| event.series_id)
+            .and_then(Self::get_by_id)
+            .ok_or(not_found.clone())?;
+
+        if series.creator_id != ic_cdk::api::msg_caller() {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only creator can update the series".to_string(),
+            });
+        }
+
+        let index = SERIES_EVENTS.with(|series_events| {
+            series_events
+                .borrow()
+                .range((series.id, 0)..=(series.id, u32::MAX))
+                .find(|entry| entry.value() == event_id)
+                .map(|entry| entry.key().1)
+        });
+        match index {
+            Some(index) => Ok((series, index)),
+            None => Err(not_found),
+        }
+    }
+
+    // Apply an update to the fields copied to new occurrences
+    fn apply_update(&mut self, update: &EventUpdate) -> Result<(), Error> {
+        let template = &mut self.template;
+        if let Some(title) = &update.title {
+            if title.trim().is_empty() {
+                return Err(Error {
+                    code: 400,
+                    error: "Title cannot be empty".to_string(),
+                    message: "Event title cannot be empty".to_string(),
+                });
+            }
+            template.title = title.clone();
+        }
+        if let Some(location) = &update.location {
+            if location.description.trim().is_empty() {
+                return Err(Error {
+                    code: 400,
+                    error: "Location description cannot be empty".to_string(),
+                    message: "Event location description cannot be empty".to_string(),
+                });
+            }
+            location.validate_coordinates()?;
+            template.location = location.clone();
+        }
+        if let Some(description) = &update.description {
+            template.description = Some(description.clone());
+        }
+        if let Some(duration_hours) = update.duration_hours {
+            template.duration_hours = duration_hours;
+        }
+        if let Some(max_participants) = update.max_participants {
+            template.max_participants = Some(max_participants);
+        }
+        if let Some(sport) = &update.sport {
+            template.sport = sport.clone();
+        }
+        if let Some(images) = &update.images {
+            template.images = images.clone();
+        }
+        if let Some(cost_per_person) = update.cost_per_person {
+            template.cost_per_person = Some(cost_per_person);
+        }
+        if let Some(requirements) = &update.requirements {
+            template.requirements = requirements.clone();
+        }
+        if let Some(visibility) = &update.visibility {
+            template.visibility = Some(visibility.clone());
+        }
+        if let Some(requires_approval) = update.requires_approval {
+            template.requires_approval = Some(requires_approval);
+        }
+        Ok(())
+    }
+}
+
+fn validate_rule(rule: &RecurrenceRule, first: u64) -> Result<(), Error> {
+    let too_many = Error {
+        code: 400,
+        error: "Invalid recurrence".to_string(),
+        message: format!("A series can have at most {} occurrences", MAX_OCCURRENCES),
+    };
+    match rule.end {
+        RecurrenceEnd::Count(0) => Err(Error {
+            code: 400,
+            error: "Invalid recurrence".to_string(),
+            message: "A series needs at least one occurrence".to_string(),
+        }),
+        RecurrenceEnd::Count(count) if count > MAX_OCCURRENCES => Err(too_many),
+        RecurrenceEnd::Until(until) if seconds_to_nanoseconds(until) < first => Err(Error {
+            code: 400,
+            error: "Invalid recurrence".to_string(),
+            message: "The series must end after its first occurrence".to_string(),
+        }),
+        RecurrenceEnd::Until(until)
+            if nth_date(&rule.frequency, first, MAX_OCCURRENCES)
+                <= seconds_to_nanoseconds(until) =>
+        {
+            Err(too_many)
+        }
+        _ => Ok(()),
+    }
+}
+
+// Date of the occurrence `index` of a series starting at `first`
+fn nth_date(frequency: &RecurrenceFrequency, first: u64, index: u32) -> u64 {
+    match frequency {
+        RecurrenceFrequency::Weekly => first + index as u64 * 7 * NANOS_PER_DAY,
+        RecurrenceFrequency::Biweekly => first + index as u64 * 14 * NANOS_PER_DAY,
+        RecurrenceFrequency::Monthly => add_months(first, index),
+    }
+}
+
+// Same local time `months` later, on the last day of the month when
+// the day does not exist (e.g. 31 January + 1 month is 28 or 29 February)
+fn add_months(date: u64, months: u32) -> u64 {
+    let offset = seconds_to_nanoseconds(CAIRO_UTC_OFFSET_SECONDS);
+    let local = date + offset;
+    let (year, month, day) = civil_from_days((local / NANOS_PER_DAY) as i64);
+
+    let month_index = year * 12 + (month as i64 - 1) + months as i64;
+    let (year, month) = (
+        month_index.div_euclid(12),
+        month_index.rem_euclid(12) as u32 + 1,
+    );
+    let day = day.min(days_in_month(year, month));
+
+    let days = days_from_civil(year, month, day) as u64;
+    (days * NANOS_PER_DAY + local % NANOS_PER_DAY).saturating_sub(offset)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 to (year, month, day), proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,374 @@
+use crate::services::match_result::sport_key;
+use crate::storage::{EVENTS, MATCH_RESULTS, RATING_HISTORY, SKILL_RATINGS};
+use crate::types::event::{Event, EventStatus};
+use crate::types::match_result::{MatchResult, MatchResultStatus};
+use crate::types::skill::{RatingChange, SkillLevel, SkillRating};
+use crate::types::sport::Sports;
+use crate::utils::get_user_skill_levels;
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_cdk::futures::spawn;
+use std::f64::consts::{LN_10, PI};
+use std::time::Duration;
+
+const DEFAULT_RATING: f64 = 1500.0;
+// Deviation of a user nothing is known about
+const MAX_DEVIATION: f64 = 350.0;
+// Deviation of a rating seeded from a declared level
+const DECLARED_DEVIATION: f64 = 250.0;
+// Keeps ratings of regular players moving
+const MIN_DEVIATION: f64 = 30.0;
+// Growth of the deviation per inactive rating period, back to MAX_DEVIATION
+// after about two and a half years without a match
+const DEVIATION_GROWTH: f64 = 63.2;
+const RATING_PERIOD: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+const Q: f64 = LN_10 / 400.0;
+
+impl SkillLevel {
+    fn rating(&self) -> f64 {
+        match self {
+            SkillLevel::Beginner => 1200.0,
+            SkillLevel::Intermediate => 1500.0,
+            SkillLevel::Advanced => 1800.0,
+            SkillLevel::Expert => 2100.0,
+        }
+    }
+}
+
+// Weight of an opponent, lower when their rating is uncertain
+fn g(deviation: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * Q * Q * deviation * deviation / (PI * PI)).sqrt()
+}
+
+fn expected_score(rating: f64, opponent_rating: f64, opponent_g: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf(-opponent_g * (rating - opponent_rating) / 400.0))
+}
+
+// Glicko update of one player over a rating period, `results` are
+// (opponent rating, opponent deviation, score) with a score of 1 for a win,
+// 0.5 for a draw and 0 for a loss. Returns the new (rating, deviation)
+fn glicko_update(rating: f64, deviation: f64, results: &[(f64, f64, f64)]) -> (f64, f64) {
+    let mut variance_inverse = 0.0;
+    let mut improvement = 0.0;
+    for &(opponent_rating, opponent_deviation, score) in results {
+        let weight = g(opponent_deviation);
+        let expected = expected_score(rating, opponent_rating, weight);
+        variance_inverse += Q * Q * weight * weight * expected * (1.0 - expected);
+        improvement += weight * (score - expected);
+    }
+
+    let precision = 1.0 / (deviation * deviation) + variance_inverse;
+    (
+        rating + Q / precision * improvement,
+        (1.0 / precision).sqrt().max(MIN_DEVIATION),
+    )
+}
+
+impl SkillRating {
+    fn new(sport: Sports, rating: f64, deviation: f64) -> SkillRating {
+        SkillRating {
+            sport,
+            rating,
+            deviation,
+            matches: 0,
+            updated_at: time(),
+        }
+    }
+
+    // The deviation grows back while the user does not play
+    fn at(mut self, now: u64) -> SkillRating {
+        let periods = now.saturating_sub(self.updated_at) as f64 / RATING_PERIOD as f64;
+        self.deviation = (self.deviation * self.deviation
+            + DEVIATION_GROWTH * DEVIATION_GROWTH * periods)
+            .sqrt()
+            .min(MAX_DEVIATION);
+        self
+    }
+
+    // Stored rating, or the default one for a user who never played the sport
+    pub fn rating_of(user_id: Principal, sport: &Sports) -> SkillRating {
+        SKILL_RATINGS
+            .with(|ratings| ratings.borrow().get(&(user_id, sport_key(sport))))
+            .map(|rating| rating.at(time()))
+            .unwrap_or_else(|| SkillRating::new(sport.clone(), DEFAULT_RATING, MAX_DEVIATION))
+    }
+
+    pub fn get_for_user(user_id: Principal) -> Vec<SkillRating> {
+        let now = time();
+        SKILL_RATINGS.with(|ratings| {
+            ratings
+                .borrow()
+                .range((user_id, String::new())..)
+                .take_while(|entry| entry.key().0 == user_id)
+                .map(|entry| entry.value().at(now))
+                .collect()
+        })
+    }
+
+    // Oldest first, one entry per rated match
+    pub fn get_history(user_id: Principal, sport: &Sports) -> Vec<RatingChange> {
+        let sport = sport_key(sport);
+        RATING_HISTORY.with(|history| {
+            history
+                .borrow()
+                .range((user_id, sport.clone(), 0)..)
+                .take_while(|entry| entry.key().0 == user_id && entry.key().1 == sport)
+                .map(|entry| entry.value())
+                .collect()
+        })
+    }
+
+    // Start the rating of players new to the sport from their declared level.
+    // Players the user canister cannot answer for are left unrated and get
+    // the default rating when they are rated
+    pub async fn seed(players: Vec<Principal>, sport: Sports) {
+        let key = sport_key(&sport);
+        for user_id in players {
+            let rated = SKILL_RATINGS
+                .with(|ratings| ratings.borrow().contains_key(&(user_id, key.clone())));
+            if rated {
+                continue;
+            }
+            let Ok(levels) = get_user_skill_levels(user_id).await else {
+                continue;
+            };
+
+            let rating = match levels.into_iter().find(|level| level.sport == sport) {
+                Some(level) => {
+                    SkillRating::new(sport.clone(), level.level.rating(), DECLARED_DEVIATION)
+                }
+                None => SkillRating::new(sport.clone(), DEFAULT_RATING, MAX_DEVIATION),
+            };
+            SKILL_RATINGS.with(|ratings| {
+                let mut ratings = ratings.borrow_mut();
+                // Another call may have rated the player meanwhile
+                if !ratings.contains_key(&(user_id, key.clone())) {
+                    ratings.insert((user_id, key.clone()), rating);
+                }
+            });
+        }
+    }
+
+    // Seed players joining an event, so the skill filters and the team
+    // balancing see their declared level before their first rated match
+    pub fn seed_in_background(players: Vec<Principal>, sport: Sports) {
+        if players.is_empty() {
+            return;
+        }
+        ic_cdk_timers::set_timer(Duration::from_nanos(1), move || {
+            spawn(Self::seed(players, sport));
+        });
+    }
+
+    // Seed the participants and waitlists of events that are not over yet,
+    // for events joined before players were seeded on join
+    pub fn backfill() {
+        let events: Vec<(Vec<Principal>, Sports)> = EVENTS.with(|events| {
+            events
+                .borrow()
+                .values()
+                .filter(|event| {
+                    matches!(
+                        event.status,
+                        EventStatus::Upcoming | EventStatus::InProgress
+                    )
+                })
+                .map(|event| {
+                    let mut players = event.participants;
+                    players.extend(event.waitlist);
+                    (players, event.sport)
+                })
+                .collect()
+        });
+        for (players, sport) in events {
+            Self::seed_in_background(players, sport);
+        }
+    }
+
+    // Rate the players of a confirmed result. Every team plays every other
+    // team, represented by the average rating of its players. Only events
+    // with teams are rated
+    pub async fn rate_match(event_id: u64) {
+        let Some(event) = Event::get_by_id(event_id) else {
+            return;
+        };
+        let players: Vec<Principal> = event
+            .teams
+            .iter()
+            .flatten()
+            .flat_map(|team| team.players.iter().copied())
+            .collect();
+        if players.is_empty() {
+            return;
+        }
+        Self::seed(players, event.sport.clone()).await;
+
+        let Some(result) = MATCH_RESULTS.with(|results| results.borrow().get(&event_id)) else {
+            return;
+        };
+        if result.status != MatchResultStatus::Confirmed {
+            return;
+        }
+        let now = time();
+        let teams: Vec<(u32, Vec<(Principal, SkillRating)>)> = result
+            .scores
+            .iter()
+            .filter_map(|score| {
+                let team = event
+                    .teams
+                    .iter()
+                    .flatten()
+                    .find(|team| team.name == score.team)?;
+                let players = team
+                    .players
+                    .iter()
+                    .map(|&user_id| (user_id, Self::rating_of(user_id, &result.sport)))
+                    .collect::<Vec<_>>();
+                (!players.is_empty()).then_some((score.score, players))
+            })
+            .collect();
+        if teams.len() < 2 {
+            return;
+        }
+
+        // (average rating, deviation) of each team before the match
+        let opponents: Vec<(f64, f64)> = teams
+            .iter()
+            .map(|(_, players)| {
+                let count = players.len() as f64;
+                let rating = players.iter().map(|(_, r)| r.rating).sum::<f64>() / count;
+                let variance = players
+                    .iter()
+                    .map(|(_, r)| r.deviation * r.deviation)
+                    .sum::<f64>()
+                    / count;
+                (rating, variance.sqrt())
+            })
+            .collect();
+
+        for (index, (score, players)) in teams.iter().enumerate() {
+            let results: Vec<(f64, f64, f64)> = teams
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != index)
+                .map(|(other, (other_score, _))| {
+                    let (opponent_rating, opponent_deviation) = opponents[other];
+                    let actual = match score.cmp(other_score) {
+                        std::cmp::Ordering::Greater => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Less => 0.0,
+                    };
+                    (opponent_rating, opponent_deviation, actual)
+                })
+                .collect();
+
+            for (user_id, rating) in players {
+                let (new_rating, new_deviation) =
+                    glicko_update(rating.rating, rating.deviation, &results);
+                let updated = SkillRating {
+                    sport: result.sport.clone(),
+                    rating: new_rating,
+                    deviation: new_deviation,
+                    matches: rating.matches + 1,
+                    updated_at: now,
+                };
+                record(*user_id, &result, updated);
+            }
+        }
+    }
+}
+
+fn record(user_id: Principal, result: &MatchResult, rating: SkillRating) {
+    let key = sport_key(&rating.sport);
+    RATING_HISTORY.with(|history| {
+        history.borrow_mut().insert(
+            (user_id, key.clone(), rating.matches),
+            RatingChange {
+                event_id: result.event_id,
+                rating: rating.rating,
+                deviation: rating.deviation,
+                recorded_at: rating.updated_at,
+            },
+        )
+    });
+    SKILL_RATINGS.with(|ratings| ratings.borrow_mut().insert((user_id, key), rating));
+}
+
+impl Event {
+    // Average rating of the participants for the sport of the event
+    pub fn average_skill(&self) -> Option<f64> {
+        if self.participants.is_empty() {
+            return None;
+        }
+        let total: f64 = self
+            .participants
+            .iter()
+            .map(|&user_id| SkillRating::rating_of(user_id, &self.sport).rating)
+            .sum();
+        Some(total / self.participants.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(deviation: f64, updated_at: u64) -> SkillRating {
+        SkillRating {
+            sport: Sports::Football,
+            rating: DEFAULT_RATING,
+            deviation,
+            matches: 3,
+            updated_at,
+        }
+    }
+
+    // Example from Glickman, "The Glicko system": a 1500 (RD 200) player
+    // beats a 1400 (RD 30) one and loses to 1550 (RD 100) and 1700 (RD 300)
+    #[test]
+    fn matches_the_glicko_paper_example() {
+        let (new_rating, new_deviation) = glicko_update(
+            1500.0,
+            200.0,
+            &[
+                (1400.0, 30.0, 1.0),
+                (1550.0, 100.0, 0.0),
+                (1700.0, 300.0, 0.0),
+            ],
+        );
+        assert!((new_rating - 1464.1).abs() < 0.1, "rating {}", new_rating);
+        assert!(
+            (new_deviation - 151.4).abs() < 0.1,
+            "deviation {}",
+            new_deviation
+        );
+    }
+
+    #[test]
+    fn update_keeps_the_minimum_deviation() {
+        let results = vec![(1500.0, 30.0, 0.5); 50];
+        let (_, deviation) = glicko_update(1500.0, MIN_DEVIATION, &results);
+        assert_eq!(deviation, MIN_DEVIATION);
+    }
+
+    #[test]
+    fn deviation_grows_back_while_inactive() {
+        let grown = rating(50.0, 0).at(RATING_PERIOD);
+        let expected = (50.0_f64 * 50.0 + DEVIATION_GROWTH * DEVIATION_GROWTH).sqrt();
+        assert!((grown.deviation - expected).abs() < 1e-9);
+        assert_eq!(grown.rating, DEFAULT_RATING);
+
+        // No time passed, nothing changes
+        assert_eq!(rating(50.0, 10).at(10).deviation, 50.0);
+    }
+
+    #[test]
+    fn deviation_is_capped_at_the_maximum() {
+        let years = 10 * 12 * RATING_PERIOD;
+        assert_eq!(rating(50.0, 0).at(years).deviation, MAX_DEVIATION);
+        assert_eq!(
+            rating(MAX_DEVIATION, 0).at(RATING_PERIOD).deviation,
+            MAX_DEVIATION
+        );
+    }
+}
@@ -0,0 +1,510 @@
+use crate::storage::{EVENTS, TEAMMATE_REQUESTS};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus};
+use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::organizer::OrganizerRight;
+use crate::types::skill::SkillRating;
+use crate::types::sport::Sports;
+use crate::types::team::{Team, TeamStrategy};
+use crate::utils::notify_in_background;
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_cdk::management_canister::raw_rand;
+use sha2::{Digest, Sha256};
+
+const MAX_TEAMS: u8 = 16;
+const MAX_TEAMMATE_REQUESTS: usize = 5;
+
+impl Sports {
+    pub fn is_team_sport(&self) -> bool {
+        matches!(
+            self,
+            Sports::Football | Sports::Basketball | Sports::Volleyball | Sports::Handball
+        )
+    }
+}
+
+fn event_not_found() -> Error {
+    Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    }
+}
+
+// Order only depends on the seed, so ties between players are broken at random
+fn shuffle(players: &mut [Principal], seed: &[u8]) {
+    players.sort_by_cached_key(|player| {
+        Sha256::new()
+            .chain_update(seed)
+            .chain_update(player.as_slice())
+            .finalize()
+    });
+}
+
+fn empty_teams(team_count: u8) -> Vec<Team> {
+    (1..=team_count)
+        .map(|number| Team {
+            name: format!("Team {}", number),
+            players: Vec::new(),
+        })
+        .collect()
+}
+
+fn deal(players: Vec<Principal>, team_count: u8) -> Vec<Team> {
+    let mut teams = empty_teams(team_count);
+    let team_count = teams.len();
+    for (index, player) in players.into_iter().enumerate() {
+        teams[index % team_count].players.push(player);
+    }
+    teams
+}
+
+fn balance(players: Vec<Principal>, team_count: u8, sport: &Sports) -> Vec<Team> {
+    let rated = players
+        .into_iter()
+        .map(|player| (player, SkillRating::rating_of(player, sport).rating))
+        .collect();
+    balance_rated(rated, team_count)
+}
+
+// Best players first, each to the smallest team with the lowest total rating
+fn balance_rated(mut rated: Vec<(Principal, f64)>, team_count: u8) -> Vec<Team> {
+    rated.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut teams = empty_teams(team_count);
+    let mut totals = vec![0.0_f64; teams.len()];
+    for (player, rating) in rated {
+        let team = (0..teams.len())
+            .min_by(|&a, &b| {
+                teams[a]
+                    .players
+                    .len()
+                    .cmp(&teams[b].players.len())
+                    .then(totals[a].total_cmp(&totals[b]))
+            })
+            .expect("there are at least two teams");
+        teams[team].players.push(player);
+        totals[team] += rating;
+    }
+    teams
+}
+
+fn friend_groups(event_id: u64, players: &[Principal]) -> Vec<Vec<Principal>> {
+    let requests: Vec<(Principal, Principal)> = TEAMMATE_REQUESTS.with(|requests| {
+        let first = Principal::management_canister();
+        requests
+            .borrow()
+            .keys_range((event_id, first, first)..)
+            .take_while(|(id, _, _)| *id == event_id)
+            .map(|(_, user_id, teammate)| (user_id, teammate))
+            .collect()
+    });
+    group_friends(players, &requests)
+}
+
+// Players linked by teammate requests, in either direction, largest group first
+fn group_friends(
+    players: &[Principal],
+    requests: &[(Principal, Principal)],
+) -> Vec<Vec<Principal>> {
+    let mut group_of: Vec<usize> = (0..players.len()).collect();
+    fn root(group_of: &mut [usize], mut index: usize) -> usize {
+        while group_of[index] != index {
+            group_of[index] = group_of[group_of[index]];
+            index = group_of[index];
+        }
+        index
+    }
+
+    for &(user_id, teammate) in requests {
+        let user = players.iter().position(|&player| player == user_id);
+        let mate = players.iter().position(|&player| player == teammate);
+        if let (Some(user), Some(mate)) = (user, mate) {
+            let (user, mate) = (root(&mut group_of, user), root(&mut group_of, mate));
+            group_of[user] = mate;
+        }
+    }
+
+    let mut groups: Vec<Vec<Principal>> = Vec::new();
+    let mut group_index: Vec<Option<usize>> = vec![None; players.len()];
+    for (index, &player) in players.iter().enumerate() {
+        let group = root(&mut group_of, index);
+        match group_index[group] {
+            Some(position) => groups[position].push(player),
+            None => {
+                group_index[group] = Some(groups.len());
+                groups.push(vec![player]);
+            }
+        }
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
+    groups
+}
+
+// Each group goes to the team with the most room left, groups that fit nowhere
+// are split. Team sizes differ by at most one
+fn keep_friends_together(groups: Vec<Vec<Principal>>, team_count: u8) -> Vec<Team> {
+    let mut teams = empty_teams(team_count);
+    let players: usize = groups.iter().map(Vec::len).sum();
+    let sizes: Vec<usize> = (0..teams.len())
+        .map(|index| players / teams.len() + usize::from(index < players % teams.len()))
+        .collect();
+    let room = |teams: &[Team], index: usize| sizes[index] - teams[index].players.len();
+
+    for group in groups {
+        let roomiest = (0..teams.len())
+            .max_by_key(|&index| (room(&teams, index), std::cmp::Reverse(index)))
+            .expect("there are at least two teams");
+        if room(&teams, roomiest) >= group.len() {
+            teams[roomiest].players.extend(group);
+            continue;
+        }
+        for player in group {
+            let roomiest = (0..teams.len())
+                .max_by_key(|&index| (room(&teams, index), std::cmp::Reverse(index)))
+                .expect("there are at least two teams");
+            teams[roomiest].players.push(player);
+        }
+    }
+    teams
+}
+
+fn notify_teams(event: &Event, teams: &[Team]) {
+    for team in teams {
+        notify_in_background(
+            team.players.clone(),
+            NewNotification {
+                content: format!("You are in {} for the event: {}", team.name, event.title),
+                notification_type: NotificationType::Message,
+            },
+        );
+    }
+}
+
+impl Event {
+    // Organizers only: split the participants into `team_count` teams,
+    // replacing the previous teams
+    pub async fn generate_teams(
+        event_id: u64,
+        caller: Principal,
+        team_count: u8,
+        strategy: TeamStrategy,
+    ) -> Result<Vec<Team>, Error> {
+        let event = Self::get_for_teams(event_id, caller)?;
+        validate_team_count(&event, team_count)?;
+        // Players new to the sport start from their declared level
+        if strategy == TeamStrategy::SkillBalanced {
+            SkillRating::seed(event.participants, event.sport).await;
+        }
+
+        let seed = raw_rand().await.map_err(|e| Error {
+            code: 500,
+            error: format!("Randomness unavailable: {}", e),
+            message: "Failed to generate teams, please try again".to_string(),
+        })?;
+
+        // Participants may have changed while waiting for randomness
+        let mut event = Self::get_for_teams(event_id, caller)?;
+        validate_team_count(&event, team_count)?;
+        let mut players = event.participants.clone();
+        shuffle(&mut players, &seed);
+
+        let teams = match strategy {
+            TeamStrategy::Random => deal(players, team_count),
+            TeamStrategy::SkillBalanced => balance(players, team_count, &event.sport),
+            TeamStrategy::KeepFriendsTogether => {
+                keep_friends_together(friend_groups(event_id, &players), team_count)
+            }
+        };
+
+        notify_teams(&event, &teams);
+        event.teams = Some(teams.clone());
+        event.updated_at = time();
+        EVENTS.with(|events| events.borrow_mut().insert(event_id, event));
+        Ok(teams)
+    }
+
+    // Organizers only: exchange two players of different teams
+    pub fn swap_team_players(
+        event_id: u64,
+        caller: Principal,
+        player: Principal,
+        other_player: Principal,
+    ) -> Result<Vec<Team>, Error> {
+        let mut event = Self::get_for_teams(event_id, caller)?;
+        let mut teams = event.teams.take().unwrap_or_default();
+
+        let team_of = |teams: &[Team], user_id: Principal| {
+            teams
+                .iter()
+                .position(|team| team.players.contains(&user_id))
+        };
+        let (Some(team), Some(other_team)) =
+            (team_of(&teams, player), team_of(&teams, other_player))
+        else {
+            return Err(Error {
+                code: 404,
+                error: "Player not found".to_string(),
+                message: "Both players have to be in a team".to_string(),
+            });
+        };
+        if team == other_team {
+            return Err(Error {
+                code: 400,
+                error: "Same team".to_string(),
+                message: "The players are already in the same team".to_string(),
+            });
+        }
+
+        teams[team].players.retain(|&user_id| user_id != player);
+        teams[team].players.push(other_player);
+        teams[other_team]
+            .players
+            .retain(|&user_id| user_id != other_player);
+        teams[other_team].players.push(player);
+        for (user_id, team) in [(other_player, team), (player, other_team)] {
+            notify_in_background(
+                vec![user_id],
+                NewNotification {
+                    content: format!(
+                        "You were moved to {} for the event: {}",
+                        teams[team].name, event.title
+                    ),
+                    notification_type: NotificationType::Message,
+                },
+            );
+        }
+
+        event.teams = Some(teams.clone());
+        event.updated_at = time();
+        EVENTS.with(|events| events.borrow_mut().insert(event_id, event));
+        Ok(teams)
+    }
+
+    // Participants only: who the caller wants to play with, used by
+    // KeepFriendsTogether. Replaces the previous requests
+    pub fn set_preferred_teammates(
+        event_id: u64,
+        user_id: Principal,
+        teammates: Vec<Principal>,
+    ) -> Result<(), Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if !event.participants.contains(&user_id) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "User is not a participant of the event".to_string(),
+            });
+        }
+        if teammates.len() > MAX_TEAMMATE_REQUESTS {
+            return Err(Error {
+                code: 400,
+                error: "Too many teammates".to_string(),
+                message: format!(
+                    "At most {} teammates can be requested",
+                    MAX_TEAMMATE_REQUESTS
+                ),
+            });
+        }
+
+        TEAMMATE_REQUESTS.with(|requests| {
+            let mut requests = requests.borrow_mut();
+            let previous: Vec<(u64, Principal, Principal)> = requests
+                .keys_range((event_id, user_id, Principal::management_canister())..)
+                .take_while(|(id, user, _)| *id == event_id && *user == user_id)
+                .collect();
+            for key in previous {
+                requests.remove(&key);
+            }
+            for teammate in teammates {
+                if teammate != user_id {
+                    requests.insert((event_id, user_id, teammate), ());
+                }
+            }
+        });
+        Ok(())
+    }
+
+    // A participant who left is taken out of their team
+    pub fn remove_from_teams(&mut self, user_id: Principal) {
+        if let Some(teams) = self.teams.as_mut() {
+            for team in teams {
+                team.players.retain(|&player| player != user_id);
+            }
+        }
+    }
+
+    // Drop the teammate requests once the teams no longer change
+    pub fn remove_teammate_requests(event_id: u64) {
+        TEAMMATE_REQUESTS.with(|requests| {
+            let mut requests = requests.borrow_mut();
+            let first = Principal::management_canister();
+            let keys: Vec<(u64, Principal, Principal)> = requests
+                .keys_range((event_id, first, first)..)
+                .take_while(|(id, _, _)| *id == event_id)
+                .collect();
+            for key in keys {
+                requests.remove(&key);
+            }
+        });
+    }
+
+    fn get_for_teams(event_id: u64, caller: Principal) -> Result<Event, Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if !event.has_right(caller, &OrganizerRight::ManageParticipants) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only organizers managing participants can manage teams".to_string(),
+            });
+        }
+        if !event.sport.is_team_sport() {
+            return Err(Error {
+                code: 400,
+                error: "Not a team sport".to_string(),
+                message:
+                    "Teams are only available for football, basketball, volleyball and handball"
+                        .to_string(),
+            });
+        }
+        if matches!(
+            event.status,
+            EventStatus::Completed | EventStatus::Cancelled
+        ) {
+            return Err(Error {
+                code: 400,
+                error: "Invalid event status".to_string(),
+                message: "Cannot change the teams of an event that is completed or cancelled"
+                    .to_string(),
+            });
+        }
+        Ok(event)
+    }
+}
+
+fn validate_team_count(event: &Event, team_count: u8) -> Result<(), Error> {
+    if !(2..=MAX_TEAMS).contains(&team_count) || usize::from(team_count) > event.participants.len()
+    {
+        return Err(Error {
+            code: 400,
+            error: "Invalid team count".to_string(),
+            message: format!(
+                "Between 2 and {} teams, and no more teams than participants",
+                MAX_TEAMS
+            ),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players(count: u8) -> Vec<Principal> {
+        (1..=count)
+            .map(|id| Principal::from_slice(&[id; 29]))
+            .collect()
+    }
+
+    fn assert_sizes_balanced(teams: &[Team], players: usize) {
+        let sizes: Vec<usize> = teams.iter().map(|team| team.players.len()).collect();
+        let max = sizes.iter().max().unwrap();
+        let min = sizes.iter().min().unwrap();
+        assert!(max - min <= 1, "team sizes {:?}", sizes);
+        assert_eq!(sizes.iter().sum::<usize>(), players);
+    }
+
+    fn team_of(teams: &[Team], player: Principal) -> usize {
+        teams
+            .iter()
+            .position(|team| team.players.contains(&player))
+            .unwrap()
+    }
+
+    #[test]
+    fn deal_keeps_sizes_within_one() {
+        for (count, team_count) in [(10, 2), (11, 3), (7, 4), (3, 2)] {
+            assert_sizes_balanced(&deal(players(count), team_count), count as usize);
+        }
+    }
+
+    #[test]
+    fn balance_splits_the_best_players() {
+        let players = players(8);
+        let rated: Vec<(Principal, f64)> = players
+            .iter()
+            .enumerate()
+            .map(|(index, &player)| (player, 1000.0 + 100.0 * index as f64))
+            .collect();
+        let teams = balance_rated(rated, 2);
+        assert_sizes_balanced(&teams, 8);
+
+        // The two best players (last ones) play against each other
+        assert_ne!(team_of(&teams, players[7]), team_of(&teams, players[6]));
+    }
+
+    #[test]
+    fn balance_spreads_the_top_players_over_every_team() {
+        let players = players(9);
+        let rated: Vec<(Principal, f64)> = players
+            .iter()
+            .enumerate()
+            .map(|(index, &player)| (player, 2000.0 - 50.0 * index as f64))
+            .collect();
+        let teams = balance_rated(rated, 3);
+        assert_sizes_balanced(&teams, 9);
+
+        let mut top: Vec<usize> = players[..3]
+            .iter()
+            .map(|&player| team_of(&teams, player))
+            .collect();
+        top.sort();
+        assert_eq!(top, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn groups_friends_in_either_direction() {
+        let players = players(5);
+        let requests = [
+            (players[0], players[1]),
+            (players[2], players[1]),
+            // Not a participant, ignored
+            (players[3], Principal::from_slice(&[99; 29])),
+        ];
+        let groups = group_friends(&players, &requests);
+
+        assert_eq!(groups.len(), 3);
+        let mut friends = groups[0].clone();
+        friends.sort();
+        let mut expected = players[..3].to_vec();
+        expected.sort();
+        assert_eq!(friends, expected);
+    }
+
+    #[test]
+    fn keeps_a_group_that_fits_together() {
+        let players = players(8);
+        let requests = [(players[0], players[1]), (players[1], players[2])];
+        let teams = keep_friends_together(group_friends(&players, &requests), 2);
+        assert_sizes_balanced(&teams, 8);
+
+        let team = team_of(&teams, players[0]);
+        assert_eq!(team_of(&teams, players[1]), team);
+        assert_eq!(team_of(&teams, players[2]), team);
+    }
+
+    #[test]
+    fn splits_an_oversized_group() {
+        let players = players(7);
+        // Six friends, but seven players over two teams leave room for four at most
+        let requests: Vec<(Principal, Principal)> =
+            (1..6).map(|index| (players[0], players[index])).collect();
+        let teams = keep_friends_together(group_friends(&players, &requests), 2);
+        assert_sizes_balanced(&teams, 7);
+        for team in &teams {
+            assert!(team.players.len() <= 4);
+        }
+    }
+}
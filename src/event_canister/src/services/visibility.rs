@@ -0,0 +1,279 @@
+use crate::storage::{EVENT_INVITATIONS, INVITE_CODES};
+use crate::types::error::Error;
+use crate::types::event::{Event, EventStatus, EventVisibility};
+use crate::types::invite::EventInvites;
+use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::organizer::OrganizerRight;
+use crate::utils::{get_member_group_ids, notify_in_background};
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_cdk::management_canister::raw_rand;
+
+const MAX_INVITE_CODES: usize = 20;
+const MAX_INVITATIONS_PER_CALL: usize = 100;
+
+// Whoever is looking at events, with the social canister groups they are a member of
+pub struct Viewer {
+    pub id: Principal,
+    pub group_ids: Vec<String>,
+}
+
+impl Viewer {
+    // Group-only events are hidden when the social canister cannot be reached
+    pub async fn caller() -> Viewer {
+        let id = ic_cdk::api::msg_caller();
+        let group_ids = if id == Principal::anonymous() {
+            Vec::new()
+        } else {
+            get_member_group_ids(id).await.unwrap_or_default()
+        };
+        Viewer { id, group_ids }
+    }
+}
+
+fn event_not_found() -> Error {
+    Error {
+        code: 404,
+        error: "Event not found".to_string(),
+        message: "No event found with the given ID".to_string(),
+    }
+}
+
+// Only members can restrict an event to their group
+pub async fn validate_visibility(
+    visibility: Option<&EventVisibility>,
+    creator_id: Principal,
+) -> Result<(), Error> {
+    let Some(EventVisibility::GroupOnly(group_id)) = visibility else {
+        return Ok(());
+    };
+    if !get_member_group_ids(creator_id).await?.contains(group_id) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only members of the group can create events for it".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl Event {
+    pub fn visibility(&self) -> EventVisibility {
+        self.visibility.clone().unwrap_or_default()
+    }
+
+    fn involves(&self, user_id: Principal) -> bool {
+        self.creator_id == user_id
+            || self.is_co_organizer(user_id)
+            || self.participants.contains(&user_id)
+            || self.waitlist.contains(&user_id)
+    }
+
+    fn is_invited(&self, user_id: Principal) -> bool {
+        EVENT_INVITATIONS.with(|invitations| invitations.borrow().contains_key(&(self.id, user_id)))
+    }
+
+    // Whether the event shows up in the viewer's listings and searches
+    pub fn is_listed_for(&self, viewer: &Viewer) -> bool {
+        match self.visibility() {
+            EventVisibility::Public => true,
+            EventVisibility::GroupOnly(group_id) if viewer.group_ids.contains(&group_id) => true,
+            _ => self.involves(viewer.id) || self.is_invited(viewer.id),
+        }
+    }
+
+    // Whether the viewer can open the event, unlisted events only need the ID
+    pub fn is_visible_to(&self, viewer: &Viewer) -> bool {
+        self.visibility() == EventVisibility::Unlisted || self.is_listed_for(viewer)
+    }
+
+    // Checked before joining the event or its waitlist
+    pub async fn check_join_access(&self, user_id: Principal) -> Result<(), Error> {
+        let allowed = match self.visibility() {
+            EventVisibility::Public | EventVisibility::Unlisted => true,
+            _ if self.involves(user_id) || self.is_invited(user_id) => true,
+            EventVisibility::InviteOnly => false,
+            EventVisibility::GroupOnly(group_id) => {
+                get_member_group_ids(user_id).await?.contains(&group_id)
+            }
+        };
+        if !allowed {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "This event is only open to invited users or group members".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    // Organizers only: a code anyone can use to get invited, until it is deleted
+    pub async fn create_invite_code(event_id: u64, caller: Principal) -> Result<String, Error> {
+        Self::get_for_invites(event_id, caller)?;
+
+        let codes = INVITE_CODES.with(|codes| {
+            codes
+                .borrow()
+                .keys_range((event_id, String::new())..)
+                .take_while(|(id, _)| *id == event_id)
+                .count()
+        });
+        if codes >= MAX_INVITE_CODES {
+            return Err(Error {
+                code: 400,
+                error: "Too many invite codes".to_string(),
+                message: format!(
+                    "An event can have at most {} invite codes, delete one first",
+                    MAX_INVITE_CODES
+                ),
+            });
+        }
+
+        let bytes = raw_rand().await.map_err(|e| Error {
+            code: 500,
+            error: format!("Randomness unavailable: {}", e),
+            message: "Failed to create an invite code, please try again".to_string(),
+        })?;
+        let code: String = bytes[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        INVITE_CODES.with(|codes| codes.borrow_mut().insert((event_id, code.clone()), time()));
+        Ok(code)
+    }
+
+    pub fn delete_invite_code(event_id: u64, code: String, caller: Principal) -> Result<(), Error> {
+        Self::get_for_invites(event_id, caller)?;
+
+        INVITE_CODES
+            .with(|codes| codes.borrow_mut().remove(&(event_id, code)))
+            .map(|_| ())
+            .ok_or(Error {
+                code: 404,
+                error: "Invite code not found".to_string(),
+                message: "No invite code found for the event".to_string(),
+            })
+    }
+
+    // Organizers only: invite users directly, they are notified
+    pub fn invite(event_id: u64, caller: Principal, users: Vec<Principal>) -> Result<(), Error> {
+        let event = Self::get_for_invites(event_id, caller)?;
+        if users.len() > MAX_INVITATIONS_PER_CALL {
+            return Err(Error {
+                code: 400,
+                error: "Too many invitations".to_string(),
+                message: format!(
+                    "At most {} users can be invited at once",
+                    MAX_INVITATIONS_PER_CALL
+                ),
+            });
+        }
+
+        let now = time();
+        let invited: Vec<Principal> = EVENT_INVITATIONS.with(|invitations| {
+            let mut invitations = invitations.borrow_mut();
+            users
+                .into_iter()
+                .filter(|&user_id| user_id != Principal::anonymous() && !event.involves(user_id))
+                .filter(|&user_id| invitations.insert((event_id, user_id), now).is_none())
+                .collect()
+        });
+
+        notify_in_background(
+            invited,
+            NewNotification {
+                content: format!("You have been invited to the event: {}", event.title),
+                notification_type: NotificationType::Message,
+            },
+        );
+        Ok(())
+    }
+
+    // Redeem an invite code, the caller can then see and join the event
+    pub fn accept_invite(event_id: u64, user_id: Principal, code: String) -> Result<Event, Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+
+        let valid = INVITE_CODES.with(|codes| codes.borrow().contains_key(&(event_id, code)));
+        if !valid {
+            return Err(Error {
+                code: 404,
+                error: "Invalid invite code".to_string(),
+                message: "The invite code is wrong or was deleted".to_string(),
+            });
+        }
+
+        EVENT_INVITATIONS.with(|invitations| {
+            let mut invitations = invitations.borrow_mut();
+            if !invitations.contains_key(&(event_id, user_id)) {
+                invitations.insert((event_id, user_id), time());
+            }
+        });
+        Ok(event)
+    }
+
+    pub fn get_invites(event_id: u64, caller: Principal) -> Result<EventInvites, Error> {
+        Self::get_for_invites(event_id, caller)?;
+        Ok(invites_of(event_id))
+    }
+
+    // Drop the invitations and codes of a deleted event
+    pub fn remove_invites(event_id: u64) {
+        let invites = invites_of(event_id);
+        INVITE_CODES.with(|codes| {
+            let mut codes = codes.borrow_mut();
+            for code in invites.codes {
+                codes.remove(&(event_id, code));
+            }
+        });
+        EVENT_INVITATIONS.with(|invitations| {
+            let mut invitations = invitations.borrow_mut();
+            for user_id in invites.invited {
+                invitations.remove(&(event_id, user_id));
+            }
+        });
+    }
+
+    // Invitations are managed by the organizers while the event is not over
+    fn get_for_invites(event_id: u64, caller: Principal) -> Result<Event, Error> {
+        let event = Event::get_by_id(event_id).ok_or_else(event_not_found)?;
+        if !event.has_right(caller, &OrganizerRight::ManageParticipants) {
+            return Err(Error {
+                code: 403,
+                error: "Forbidden".to_string(),
+                message: "Only organizers managing participants can manage invitations".to_string(),
+            });
+        }
+        if matches!(
+            event.status,
+            EventStatus::Completed | EventStatus::Cancelled
+        ) {
+            return Err(Error {
+                code: 400,
+                error: "Invalid event status".to_string(),
+                message: "Cannot invite to an event that is completed or cancelled".to_string(),
+            });
+        }
+        Ok(event)
+    }
+}
+
+fn invites_of(event_id: u64) -> EventInvites {
+    let codes = INVITE_CODES.with(|codes| {
+        codes
+            .borrow()
+            .keys_range((event_id, String::new())..)
+            .take_while(|(id, _)| *id == event_id)
+            .map(|(_, code)| code)
+            .collect()
+    });
+    let invited = EVENT_INVITATIONS.with(|invitations| {
+        invitations
+            .borrow()
+            .keys_range((event_id, Principal::management_canister())..)
+            .take_while(|(id, _)| *id == event_id)
+            .map(|(_, user_id)| user_id)
+            .collect()
+    });
+    EventInvites { codes, invited }
+}
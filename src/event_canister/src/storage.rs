@@ -1,13 +1,26 @@
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
 use ic_stable_structures::storable::Bound;
 use ic_stable_structures::Storable;
-use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
 use std::borrow::Cow;
 use std::cell::RefCell;
 
-use candid::{Decode, Encode};
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
 
-use crate::types::{review::Review, event::Event};
+use crate::types::event::{Event, EventStatus};
+use crate::types::{
+    attendance::{AttendanceStats, CheckInSession},
+    location::Location,
+    match_result::{MatchResult, SportStats},
+    payment::{EscrowDeposit, EscrowTransfer},
+    quarantine::QuarantinedRecord,
+    rating::RatingSummary,
+    review::Review,
+    series::EventSeries,
+    skill::{RatingChange, SkillRating},
+    sport::Sports,
+};
+use versioned::Versioned;
 
 // Wrapper for Vec<u64> to make it Storable
 #[derive(Clone, Debug)]
@@ -29,39 +42,358 @@ impl Storable for ReviewIds {
     }
 }
 
+// Reminder offsets in seconds before the event date, 24 hours and 1 hour by default
+#[derive(Clone, Debug)]
+pub struct ReminderOffsets(pub Vec<u64>);
+
+impl Default for ReminderOffsets {
+    fn default() -> Self {
+        ReminderOffsets(vec![24 * 60 * 60, 60 * 60])
+    }
+}
+
+impl Storable for ReminderOffsets {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(&self.0).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Vec<u64>)
+            .map(ReminderOffsets)
+            .unwrap_or_default()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        Encode!(&self.0).unwrap()
+    }
+}
+
 type _Memory = VirtualMemory<DefaultMemoryImpl>;
 
+// Event shape before the waitlist (versions 0 and 1)
+#[derive(CandidType, Deserialize)]
+struct EventV1 {
+    id: u64,
+    creator_id: Principal,
+    title: String,
+    description: Option<String>,
+    event_date: u64,
+    duration_hours: u8,
+    location: Location,
+    sport: Sports,
+    max_participants: Option<u16>,
+    participants: Vec<Principal>,
+    status: EventStatus,
+    images: Vec<Vec<u8>>,
+    cost_per_person: Option<u64>,
+    requirements: Vec<String>,
+    created_at: u64,
+    updated_at: u64,
+}
+
+impl Versioned for Event {
+    const VERSION: u8 = 2;
+
+    fn upgrade(version: u8, payload: &[u8]) -> Result<Self, String> {
+        let old = Decode!(payload, EventV1)
+            .map_err(|e| format!("cannot upgrade from version {}: {}", version, e))?;
+        Ok(Event {
+            id: old.id,
+            creator_id: old.creator_id,
+            title: old.title,
+            description: old.description,
+            event_date: old.event_date,
+            duration_hours: old.duration_hours,
+            location: old.location,
+            sport: old.sport,
+            max_participants: old.max_participants,
+            participants: old.participants,
+            waitlist: Vec::new(),
+            series_id: None,
+            visibility: None,
+            requires_approval: None,
+            group_id: None,
+            co_organizers: None,
+            pending_owner: None,
+            teams: None,
+            status: old.status,
+            images: old.images,
+            cost_per_person: old.cost_per_person,
+            requirements: old.requirements,
+            created_at: old.created_at,
+            updated_at: old.updated_at,
+        })
+    }
+}
+
 // Implement Storable for Event
 impl Storable for Event {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
     }
 }
 
+impl Versioned for Review {
+    const VERSION: u8 = 1;
+}
+
 // Implement Storable for Review
 impl Storable for Review {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for RatingSummary {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for RatingSummary
+impl Storable for RatingSummary {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for EventSeries {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for EventSeries
+impl Storable for EventSeries {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for EscrowDeposit {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for EscrowDeposit
+impl Storable for EscrowDeposit {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for EscrowTransfer {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for EscrowTransfer
+impl Storable for EscrowTransfer {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for CheckInSession {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for CheckInSession
+impl Storable for CheckInSession {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for AttendanceStats {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for AttendanceStats
+impl Storable for AttendanceStats {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for MatchResult {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for MatchResult
+impl Storable for MatchResult {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for SportStats {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for SportStats
+impl Storable for SportStats {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for SkillRating {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for SkillRating
+impl Storable for SkillRating {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for RatingChange {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for RatingChange
+impl Storable for RatingChange {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for QuarantinedRecord {
+    const VERSION: u8 = 1;
+}
+
+// Implement Storable for QuarantinedRecord
+impl Storable for QuarantinedRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
     }
 }
 
@@ -90,13 +422,327 @@ thread_local! {
         )
     );
 
-    // Store all event IDs for efficient iteration
-    pub static ALL_EVENT_IDS: RefCell<StableBTreeMap<u64, ReviewIds, _Memory>> = RefCell::new(
+    // MemoryId 3 held the never used ALL_EVENT_IDS map, do not reuse it
+
+    // ID counters - only for events now (reviews use unique IDs)
+    pub static NEXT_EVENT_ID: RefCell<StableCell<u64, _Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4))),
+            1,
+        )
+    );
+
+    // Version of the stored data shapes, see `upgrade.rs`
+    pub static SCHEMA_VERSION: RefCell<StableCell<u32, _Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5))),
+            0,
+        )
+    );
+
+    // Records that failed to decode during an upgrade
+    pub static QUARANTINE: RefCell<StableBTreeMap<u64, QuarantinedRecord, _Memory>> = RefCell::new(
         StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3))),
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(6))),
         )
     );
 
-    // ID counters - only for events now (reviews use unique IDs)
-    pub static NEXT_EVENT_ID: RefCell<u64> = RefCell::new(1);
+    // (transition time, event_id) for every event that still has a status change ahead
+    pub static STATUS_SCHEDULE: RefCell<StableBTreeMap<(u64, u64), (), _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(7))),
+        )
+    );
+
+    // (reminder time, event_id) -> reminder offset in seconds
+    pub static REMINDER_SCHEDULE: RefCell<StableBTreeMap<(u64, u64), u64, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(8))),
+        )
+    );
+
+    pub static REMINDER_OFFSETS: RefCell<StableCell<ReminderOffsets, _Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(9))),
+            ReminderOffsets::default(),
+        )
+    );
+
+    // (event_id, user) -> review_id, one review per participant
+    pub static REVIEWS_BY_USER: RefCell<StableBTreeMap<(u64, Principal), u64, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(10))),
+        )
+    );
+
+    // Rating aggregates, updated with every review change
+    pub static EVENT_RATINGS: RefCell<StableBTreeMap<u64, RatingSummary, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(11))),
+        )
+    );
+
+    pub static ORGANIZER_RATINGS: RefCell<StableBTreeMap<Principal, RatingSummary, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(12))),
+        )
+    );
+
+    // Filter index (term, event_date, event_id), see `services/index.rs`
+    pub static EVENT_INDEX: RefCell<StableBTreeMap<(String, u64, u64), (), _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(13))),
+        )
+    );
+
+    pub static EVENT_SERIES: RefCell<StableBTreeMap<u64, EventSeries, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(14))),
+        )
+    );
+
+    // (series_id, occurrence number) -> event_id of the created occurrences
+    pub static SERIES_EVENTS: RefCell<StableBTreeMap<(u64, u32), u64, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(15))),
+        )
+    );
+
+    // (due, series_id) of the next occurrence to create for each series
+    pub static SERIES_SCHEDULE: RefCell<StableBTreeMap<(u64, u64), (), _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(16))),
+        )
+    );
+
+    // (event_id, participant) -> payment held in the event escrow
+    pub static ESCROW_DEPOSITS: RefCell<StableBTreeMap<(u64, Principal), EscrowDeposit, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(17))),
+        )
+    );
+
+    // (event_id, block_index) -> every escrow transfer, in ledger order
+    pub static ESCROW_TRANSFERS: RefCell<StableBTreeMap<(u64, u64), EscrowTransfer, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(18))),
+        )
+    );
+
+    // event_id -> open check-in, removed once the event is completed
+    pub static CHECK_IN_SESSIONS: RefCell<StableBTreeMap<u64, CheckInSession, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(19))),
+        )
+    );
+
+    // (event_id, participant) -> check-in time
+    pub static ATTENDANCE: RefCell<StableBTreeMap<(u64, Principal), u64, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(20))),
+        )
+    );
+
+    // user -> attendance and no-show counters
+    pub static USER_ATTENDANCE: RefCell<StableBTreeMap<Principal, AttendanceStats, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(21))),
+        )
+    );
+
+    // (event_id, invited user) -> invitation time
+    pub static EVENT_INVITATIONS: RefCell<StableBTreeMap<(u64, Principal), u64, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(22))),
+        )
+    );
+
+    // (event_id, invite code) -> creation time
+    pub static INVITE_CODES: RefCell<StableBTreeMap<(u64, String), u64, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(23))),
+        )
+    );
+
+    // (event_id, user) -> request time of pending join requests
+    pub static JOIN_REQUESTS: RefCell<StableBTreeMap<(u64, Principal), u64, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(24))),
+        )
+    );
+
+    // (event_id, participant, requested teammate), see `TeamStrategy::KeepFriendsTogether`
+    pub static TEAMMATE_REQUESTS: RefCell<StableBTreeMap<(u64, Principal, Principal), (), _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(25))),
+        )
+    );
+
+    // event_id -> match result submitted after the event completed
+    pub static MATCH_RESULTS: RefCell<StableBTreeMap<u64, MatchResult, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(26))),
+        )
+    );
+
+    // (dispute_until, event_id) of the results waiting to be confirmed
+    pub static RESULT_SCHEDULE: RefCell<StableBTreeMap<(u64, u64), (), _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(27))),
+        )
+    );
+
+    // (user, sport) -> totals over the confirmed match results
+    pub static PLAYER_STATS: RefCell<StableBTreeMap<(Principal, String), SportStats, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(28))),
+        )
+    );
+
+    // (user, sport) -> current skill rating
+    pub static SKILL_RATINGS: RefCell<StableBTreeMap<(Principal, String), SkillRating, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(29))),
+        )
+    );
+
+    // (user, sport, match number) -> rating after that match
+    pub static RATING_HISTORY: RefCell<StableBTreeMap<(Principal, String, u32), RatingChange, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(30))),
+        )
+    );
+}
+
+// Move records that no longer decode into QUARANTINE, returns how many were moved.
+// Must run before any of the scanned maps is first used
+pub fn quarantine_undecodable_records() -> usize {
+    let memory = |id: u8| MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(id)));
+
+    let records = [
+        versioned::quarantine_undecodable::<u64, Event, _>("events", memory(0)),
+        versioned::quarantine_undecodable::<u64, Review, _>("reviews", memory(1)),
+        versioned::quarantine_undecodable::<u64, EventSeries, _>("event_series", memory(14)),
+        versioned::quarantine_undecodable::<(u64, Principal), EscrowDeposit, _>(
+            "escrow_deposits",
+            memory(17),
+        ),
+        versioned::quarantine_undecodable::<(u64, u64), EscrowTransfer, _>(
+            "escrow_transfers",
+            memory(18),
+        ),
+        versioned::quarantine_undecodable::<u64, CheckInSession, _>(
+            "check_in_sessions",
+            memory(19),
+        ),
+        versioned::quarantine_undecodable::<Principal, AttendanceStats, _>(
+            "user_attendance",
+            memory(21),
+        ),
+        versioned::quarantine_undecodable::<u64, MatchResult, _>("match_results", memory(26)),
+        versioned::quarantine_undecodable::<(Principal, String), SportStats, _>(
+            "player_stats",
+            memory(28),
+        ),
+        versioned::quarantine_undecodable::<(Principal, String), SkillRating, _>(
+            "skill_ratings",
+            memory(29),
+        ),
+        versioned::quarantine_undecodable::<(Principal, String, u32), RatingChange, _>(
+            "rating_history",
+            memory(30),
+        ),
+    ]
+    .concat();
+
+    let count = records.len();
+    let quarantined_at = ic_cdk::api::time();
+    QUARANTINE.with(|quarantine| {
+        let mut quarantine = quarantine.borrow_mut();
+        for record in records {
+            let id = quarantine.last_key_value().map_or(0, |(id, _)| id + 1);
+            quarantine.insert(
+                id,
+                QuarantinedRecord {
+                    store: record.store,
+                    key: record.key,
+                    bytes: record.bytes,
+                    error: record.error,
+                    quarantined_at,
+                },
+            );
+        }
+    });
+    count
+}
+
+pub fn get_quarantined_records() -> Vec<QuarantinedRecord> {
+    QUARANTINE.with(|quarantine| quarantine.borrow().values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_v1() -> EventV1 {
+        EventV1 {
+            id: 7,
+            creator_id: Principal::from_slice(&[1; 29]),
+            title: "Morning run".to_string(),
+            description: Some("5 km".to_string()),
+            event_date: 1_700_000_000_000_000_000,
+            duration_hours: 2,
+            location: Location {
+                governorate: 1,
+                city: 2,
+                description: "Corniche".to_string(),
+                latitude: None,
+                longitude: None,
+            },
+            sport: Sports::Running,
+            max_participants: Some(10),
+            participants: vec![Principal::from_slice(&[2; 29])],
+            status: EventStatus::Upcoming,
+            images: Vec::new(),
+            cost_per_person: None,
+            requirements: vec!["Water".to_string()],
+            created_at: 1,
+            updated_at: 2,
+        }
+    }
+
+    fn assert_upgraded(event: Event) {
+        assert_eq!(event.id, 7);
+        assert_eq!(event.title, "Morning run");
+        assert_eq!(event.participants, vec![Principal::from_slice(&[2; 29])]);
+        assert_eq!(event.status, EventStatus::Upcoming);
+        assert_eq!(event.requirements, vec!["Water".to_string()]);
+        assert!(event.waitlist.is_empty());
+        assert!(event.series_id.is_none());
+        assert!(event.teams.is_none());
+    }
+
+    #[test]
+    fn upgrades_bare_candid_event_v1() {
+        let bytes = Encode!(&event_v1()).unwrap();
+        assert_upgraded(Event::from_bytes(Cow::Owned(bytes)));
+    }
+
+    #[test]
+    fn upgrades_versioned_event_v1() {
+        let mut bytes = vec![1];
+        bytes.extend(Encode!(&event_v1()).unwrap());
+        assert_upgraded(Event::from_bytes(Cow::Owned(bytes)));
+    }
+
+    #[test]
+    fn round_trips_current_event() {
+        let mut bytes = vec![1];
+        bytes.extend(Encode!(&event_v1()).unwrap());
+        let event = Event::from_bytes(Cow::Owned(bytes));
+
+        let stored = event.to_bytes().into_owned();
+        assert_eq!(stored[0], Event::VERSION);
+        assert_upgraded(Event::from_bytes(Cow::Owned(stored)));
+    }
 }
@@ -0,0 +1,32 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+// Open check-in of an event, the code shown by the organizer is derived
+// from `seed` and changes every few seconds
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct CheckInSession {
+    pub seed: Vec<u8>, // from raw_rand, never leaves the canister
+    pub opened_at: u64,
+    pub closes_at: u64, // end of the event
+}
+
+// Code to show participants (seconds for frontend)
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct CheckInCode {
+    pub code: String,
+    pub expires_at: u64,
+}
+
+// Participant of an event and when they checked in, if they did (seconds for frontend)
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct Attendance {
+    pub user_id: Principal,
+    pub checked_in_at: Option<u64>,
+}
+
+// Completed events with an opened check-in, counted per participant
+#[derive(Debug, Clone, Default, Deserialize, Serialize, CandidType)]
+pub struct AttendanceStats {
+    pub attended: u32,
+    pub no_shows: u32,
+}
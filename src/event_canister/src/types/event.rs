@@ -1,5 +1,7 @@
 use crate::types::location::Location;
+use crate::types::organizer::CoOrganizer;
 use crate::types::sport::Sports;
+use crate::types::team::Team;
 use candid::{CandidType, Principal};
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +14,16 @@ pub enum EventStatus {
     Cancelled,  // cancelled
 }
 
+// Who can find and join an event, the creator and participants always can
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq, Default)]
+pub enum EventVisibility {
+    #[default]
+    Public, // listed, anyone can join
+    Unlisted,          // not listed, anyone with the event ID can see and join it
+    InviteOnly,        // invited principals and invite code holders
+    GroupOnly(String), // members of the social canister group
+}
+
 // Main Event struct
 #[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
 pub struct Event {
@@ -19,12 +31,23 @@ pub struct Event {
     pub creator_id: Principal,
     pub title: String,
     pub description: Option<String>,
-    pub event_date: u64,               // timestamp
-    pub duration_hours: u8,            // duration in hours
-    pub location: Location,            // location with governorate, city, description
-    pub sport: Sports,                 // sport type
-    pub max_participants: Option<u16>, // maximum participants
-    pub participants: Vec<Principal>,  // participants list
+    pub event_date: u64,                     // timestamp
+    pub duration_hours: u8,                  // duration in hours
+    pub location: Location,                  // location with governorate, city, description
+    pub sport: Sports,                       // sport type
+    pub max_participants: Option<u16>,       // maximum participants
+    pub participants: Vec<Principal>,        // participants list
+    pub waitlist: Vec<Principal>,            // waiting for a free spot, oldest first
+    pub series_id: Option<u64>,              // set for occurrences of a recurring series
+    pub visibility: Option<EventVisibility>, // None for events created before visibility, Public
+    pub requires_approval: Option<bool>,     // joining creates a request the creator reviews
+    pub group_id: Option<String>,            // social canister group organizing the event
+    // Help the creator run the event, with the rights they were given
+    pub co_organizers: Option<Vec<CoOrganizer>>,
+    // Asked by the creator to take the event over, until they accept
+    pub pending_owner: Option<Principal>,
+    // Latest generate_teams result, for team sports
+    pub teams: Option<Vec<Team>>,
     pub status: EventStatus,
     pub images: Vec<Vec<u8>>,         // event images
     pub cost_per_person: Option<u64>, // cost per person
@@ -46,10 +69,13 @@ pub struct CreateEventInput {
     pub images: Vec<Vec<u8>>,
     pub cost_per_person: Option<u64>,
     pub requirements: Vec<String>,
+    pub visibility: Option<EventVisibility>, // Public when not set
+    pub requires_approval: Option<bool>,
+    pub group_id: Option<String>, // the creator must be a member of the group
 }
 
 // EventUpdate struct for updating Event data
-#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, Default)]
 pub struct EventUpdate {
     pub title: Option<String>,
     pub description: Option<String>,
@@ -62,4 +88,6 @@ pub struct EventUpdate {
     pub images: Option<Vec<Vec<u8>>>,
     pub cost_per_person: Option<u64>,
     pub requirements: Option<Vec<String>>,
+    pub visibility: Option<EventVisibility>,
+    pub requires_approval: Option<bool>,
 }
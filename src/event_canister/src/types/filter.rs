@@ -10,6 +10,28 @@ pub struct EventFilter {
     pub sport: Option<Sports>,
     pub status: Option<EventStatus>,
     pub cost_filter: Option<CostFilter>,
+    pub min_rating: Option<f64>, // minimum average rating, unrated events are excluded
+    // Start of the event date range, inclusive (seconds)
+    pub from_date: Option<u64>,
+    // End of the event date range, inclusive (seconds)
+    pub to_date: Option<u64>,
+    // true for events with free spots, false for full ones
+    pub has_free_spots: Option<bool>,
+    // Only events falling on one of the caller's free days (user profile),
+    // ignored when the caller has none or they cannot be fetched
+    pub on_free_days: Option<bool>,
+    // Every word must appear in the title, description or requirements,
+    // case-insensitive and with Arabic text normalized
+    pub keyword: Option<String>,
+    // Average skill rating of the participants for the sport of the event,
+    // events without participants are excluded
+    pub skill_range: Option<SkillRange>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct SkillRange {
+    pub min: f64,
+    pub max: f64,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
@@ -18,3 +40,20 @@ pub enum CostFilter {
     Paid,                         // cost_per_person is Some
     Range { min: u64, max: u64 }, // cost range filter
 }
+
+// Sort orders for list_events
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq, Default)]
+pub enum EventSort {
+    #[default]
+    EventDate,         // soonest first
+    CreatedAt,         // newest first
+    RemainingCapacity, // most free spots first, unlimited events first
+    Rating,            // best average rating first
+}
+
+// Position after the last returned event, pass it back unchanged
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct EventCursor {
+    pub sort_key: u64,
+    pub id: u64,
+}
@@ -0,0 +1,12 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+// Group-level permissions of the owners canister group admins
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum GroupPermission {
+    ManageMembers,
+    ModerateContent,
+    ManageEvents,
+    ConfigureGroup,
+    ViewGroupAnalytics,
+}
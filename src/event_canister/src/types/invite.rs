@@ -0,0 +1,9 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+// Open invitations of an invite-only event
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct EventInvites {
+    pub codes: Vec<String>,      // codes that can still be accepted
+    pub invited: Vec<Principal>, // invited users, whether they joined or not
+}
@@ -0,0 +1,9 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+// Pending request to join an event in approval mode
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct JoinRequest {
+    pub user_id: Principal,
+    pub requested_at: u64, // In seconds for frontend
+}
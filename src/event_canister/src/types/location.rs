@@ -8,6 +8,8 @@ pub struct Location {
     pub governorate: u8,
     pub city: u16,
     pub description: String,
+    pub latitude: Option<f64>,  // degrees, set together with longitude
+    pub longitude: Option<f64>, // degrees, set together with latitude
 }
 
 impl Storable for Location {
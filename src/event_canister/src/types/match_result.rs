@@ -0,0 +1,64 @@
+use crate::types::sport::Sports;
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum MatchResultStatus {
+    Pending,   // can be disputed until `dispute_until`
+    Disputed,  // waits for the organizers to submit a corrected result
+    Confirmed, // counted in the player stats, cannot change anymore
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct TeamScore {
+    pub team: String, // team name, one of the event teams when it has any
+    pub score: u32,
+}
+
+// What a player did in one match
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct PlayerMatchStats {
+    pub user_id: Principal,
+    pub goals: u32,
+    pub assists: u32,
+    pub mvp: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct MatchResultInput {
+    pub scores: Vec<TeamScore>,
+    pub players: Vec<PlayerMatchStats>, // players without stats can be left out
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct ResultDispute {
+    pub user_id: Principal,
+    pub reason: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct MatchResult {
+    pub event_id: u64,
+    pub sport: Sports,
+    pub scores: Vec<TeamScore>,
+    pub players: Vec<PlayerMatchStats>,
+    pub status: MatchResultStatus,
+    pub disputes: Vec<ResultDispute>, // of the latest submission
+    pub submitted_by: Principal,
+    pub submitted_at: u64,
+    pub dispute_until: u64,
+}
+
+// Totals of a user for one sport, over their confirmed match results
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct SportStats {
+    pub sport: Sports,
+    pub matches_played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub goals: u32,
+    pub assists: u32,
+    pub mvp_awards: u32,
+}
@@ -1,10 +1,22 @@
+pub mod attendance;
 pub mod city;
 pub mod error;
 pub mod event;
 pub mod filter;
 pub mod governorate;
+pub mod group_admin;
+pub mod invite;
+pub mod join_request;
 pub mod location;
+pub mod match_result;
 pub mod notification;
+pub mod organizer;
+pub mod payment;
+pub mod quarantine;
+pub mod rating;
 pub mod response;
 pub mod review;
+pub mod series;
+pub mod skill;
 pub mod sport;
+pub mod team;
@@ -1,4 +1,5 @@
-use candid::CandidType;
+use crate::types::error::Error;
+use candid::{CandidType, Principal};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
@@ -14,3 +15,39 @@ pub struct NewNotification {
     pub content: String,
     pub notification_type: NotificationType,
 }
+
+// * errors returned by the user canister when delivering notifications
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub enum NotificationDeliveryError {
+    UnauthorizedCaller(Principal),
+    RecipientNotFound(Principal),
+    InvalidNotification(String),
+    Internal(String),
+}
+
+impl From<NotificationDeliveryError> for Error {
+    fn from(error: NotificationDeliveryError) -> Self {
+        match error {
+            NotificationDeliveryError::UnauthorizedCaller(caller) => Error {
+                code: 403,
+                error: format!("Canister {} is not allowed to deliver notifications", caller),
+                message: "Failed to add notification".into(),
+            },
+            NotificationDeliveryError::RecipientNotFound(recipient) => Error {
+                code: 404,
+                error: format!("Notification recipient {} not found", recipient),
+                message: "Failed to add notification".into(),
+            },
+            NotificationDeliveryError::InvalidNotification(reason) => Error {
+                code: 400,
+                error: reason,
+                message: "Failed to add notification".into(),
+            },
+            NotificationDeliveryError::Internal(reason) => Error {
+                code: 500,
+                error: reason,
+                message: "Failed to add notification".into(),
+            },
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+// What a co-organizer can do, the creator can do everything
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum OrganizerRight {
+    EditDetails,        // update the event, but not cancel or delete it
+    ManageParticipants, // join requests and invitations
+    CheckIn,            // run the check-in and see the attendance
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct CoOrganizer {
+    pub user_id: Principal,
+    pub rights: Vec<OrganizerRight>,
+}
@@ -0,0 +1,30 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum TransferKind {
+    Deposit, // participant to the event escrow, when joining
+    Refund,  // event escrow back to the participant, minus the ledger fee
+    Release, // event escrow to the creator after the event completed, minus the ledger fee
+}
+
+// Payment of a participant held in the event escrow
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct EscrowDeposit {
+    pub amount: u64,
+    pub block_index: u64, // ledger block of the deposit
+    pub forfeited: bool,  // left after the refund deadline, released to the creator
+    pub created_at: u64,
+}
+
+// Ledger transfer into or out of an event escrow
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct EscrowTransfer {
+    pub event_id: u64,
+    pub kind: TransferKind,
+    pub participant: Principal, // whose deposit was moved
+    pub recipient: Principal,   // escrow owner for deposits, otherwise who was paid
+    pub amount: u64,            // amount received by the recipient
+    pub block_index: u64,
+    pub created_at: u64,
+}
@@ -0,0 +1,11 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+// Rating aggregate of an event or of all events of an organizer
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, Default)]
+pub struct RatingSummary {
+    pub count: u64,
+    pub sum: u64,
+    pub average: f64,        // 0 when there are no reviews
+    pub histogram: Vec<u64>, // histogram[i] is the number of (i + 1) star reviews
+}
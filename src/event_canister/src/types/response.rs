@@ -7,7 +7,7 @@ pub fn nanoseconds_to_seconds(nanos: u64) -> u64 {
 }
 
 pub fn seconds_to_nanoseconds(seconds: u64) -> u64 {
-    seconds * 1_000_000_000
+    seconds.saturating_mul(1_000_000_000)
 }
 
 pub fn format_timestamp(nanos: u64) -> String {
@@ -30,10 +30,19 @@ pub struct EventResponse {
     pub sport: crate::types::sport::Sports,
     pub max_participants: Option<u16>,
     pub participants: Vec<candid::Principal>,
+    pub waitlist: Vec<candid::Principal>,
+    pub series_id: Option<u64>,
+    pub visibility: crate::types::event::EventVisibility,
+    pub requires_approval: bool,
+    pub group_id: Option<String>,
+    pub co_organizers: Vec<crate::types::organizer::CoOrganizer>,
+    pub pending_owner: Option<candid::Principal>,
+    pub teams: Vec<crate::types::team::Team>,
     pub status: crate::types::event::EventStatus,
     pub images: Vec<Vec<u8>>,
     pub cost_per_person: Option<u64>,
     pub requirements: Vec<String>,
+    pub rating: crate::types::rating::RatingSummary,
     pub created_at: u64,          // In seconds for frontend
     pub updated_at: u64,          // In seconds for frontend
 }
@@ -52,12 +61,87 @@ impl From<crate::types::event::Event> for EventResponse {
             sport: event.sport,
             max_participants: event.max_participants,
             participants: event.participants,
+            waitlist: event.waitlist,
+            series_id: event.series_id,
+            visibility: event.visibility.unwrap_or_default(),
+            requires_approval: event.requires_approval.unwrap_or_default(),
+            group_id: event.group_id,
+            co_organizers: event.co_organizers.unwrap_or_default(),
+            pending_owner: event.pending_owner,
+            teams: event.teams.unwrap_or_default(),
             status: event.status,
             images: event.images,
             cost_per_person: event.cost_per_person,
             requirements: event.requirements,
+            rating: crate::types::rating::RatingSummary::for_event(event.id),
             created_at: nanoseconds_to_seconds(event.created_at),
             updated_at: nanoseconds_to_seconds(event.updated_at),
         }
     }
 }
+
+// Lightweight event for listings, without image bytes
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct EventSummary {
+    pub id: u64,
+    pub creator_id: candid::Principal,
+    pub title: String,
+    pub event_date: u64, // In seconds for frontend
+    pub duration_hours: u8,
+    pub location: crate::types::location::Location,
+    pub sport: crate::types::sport::Sports,
+    pub max_participants: Option<u16>,
+    pub participants_count: u32,
+    pub waitlist_count: u32,
+    pub series_id: Option<u64>,
+    pub visibility: crate::types::event::EventVisibility,
+    pub requires_approval: bool,
+    pub group_id: Option<String>,
+    pub status: crate::types::event::EventStatus,
+    pub images_count: u32,
+    pub cost_per_person: Option<u64>,
+    pub rating_average: f64,
+    pub rating_count: u64,
+    pub created_at: u64, // In seconds for frontend
+}
+
+impl From<crate::types::event::Event> for EventSummary {
+    fn from(event: crate::types::event::Event) -> Self {
+        let rating = crate::types::rating::RatingSummary::for_event(event.id);
+        Self {
+            id: event.id,
+            creator_id: event.creator_id,
+            title: event.title,
+            event_date: nanoseconds_to_seconds(event.event_date),
+            duration_hours: event.duration_hours,
+            location: event.location,
+            sport: event.sport,
+            max_participants: event.max_participants,
+            participants_count: event.participants.len() as u32,
+            waitlist_count: event.waitlist.len() as u32,
+            series_id: event.series_id,
+            visibility: event.visibility.unwrap_or_default(),
+            requires_approval: event.requires_approval.unwrap_or_default(),
+            group_id: event.group_id,
+            status: event.status,
+            images_count: event.images.len() as u32,
+            cost_per_person: event.cost_per_person,
+            rating_average: rating.average,
+            rating_count: rating.count,
+            created_at: nanoseconds_to_seconds(event.created_at),
+        }
+    }
+}
+
+// Result of a "near me" search
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct NearbyEvent {
+    pub event: EventSummary,
+    pub distance_km: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct EventPage {
+    pub events: Vec<EventSummary>,
+    pub next_cursor: Option<crate::types::filter::EventCursor>,
+}
@@ -10,6 +10,7 @@ pub struct Review {
     pub rating: u8,           // 1 to 5 stars
     pub comment: Option<String>,
     pub created_at: u64,
+    pub updated_at: Option<u64>, // set when the author edits the review
 }
 
 // Input struct for creating reviews
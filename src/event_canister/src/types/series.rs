@@ -0,0 +1,50 @@
+use crate::types::event::CreateEventInput;
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum RecurrenceFrequency {
+    Weekly,   // every 7 days
+    Biweekly, // every 14 days
+    Monthly,  // same day of the month, the last day for shorter months
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum RecurrenceEnd {
+    Until(u64), // last possible occurrence date, inclusive (seconds)
+    Count(u32), // number of occurrences
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct RecurrenceRule {
+    pub frequency: RecurrenceFrequency,
+    pub end: RecurrenceEnd,
+}
+
+// Struct for creating a series (input from frontend),
+// `event.event_date` is the date of the first occurrence
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct CreateEventSeriesInput {
+    pub event: CreateEventInput,
+    pub rule: RecurrenceRule,
+}
+
+// Recurring event, occurrences are created as regular events
+// a few weeks ahead of their date
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct EventSeries {
+    pub id: u64,
+    pub creator_id: Principal,
+    pub template: CreateEventInput, // fields copied to every new occurrence
+    pub rule: RecurrenceRule,
+    pub materialized: u32, // occurrences created so far
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+// Which occurrences a series-level edit or cancellation applies to
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum SeriesScope {
+    ThisOccurrence, // only the given occurrence
+    AllFuture,      // the given occurrence and every later one
+}
@@ -0,0 +1,37 @@
+use crate::types::sport::Sports;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+// Self-declared on the user profile, seeds the rating of a sport before any match
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum SkillLevel {
+    Beginner,
+    Intermediate,
+    Advanced,
+    Expert,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct SportLevel {
+    pub sport: Sports,
+    pub level: SkillLevel,
+}
+
+// Glicko rating of a user for one sport
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct SkillRating {
+    pub sport: Sports,
+    pub rating: f64,
+    pub deviation: f64, // shrinks with every match, grows back while the user does not play
+    pub matches: u32,   // rated matches, 0 for a rating seeded from the declared level
+    pub updated_at: u64,
+}
+
+// Rating after one match, for charting
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct RatingChange {
+    pub event_id: u64,
+    pub rating: f64,
+    pub deviation: f64,
+    pub recorded_at: u64,
+}
@@ -0,0 +1,16 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+// How generate_teams splits the participants
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType, PartialEq)]
+pub enum TeamStrategy {
+    Random,              // shuffled and dealt out
+    SkillBalanced,       // close total skill rating for the sport, declared level until rated
+    KeepFriendsTogether, // requested teammates play together when the team sizes allow
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct Team {
+    pub name: String,
+    pub players: Vec<Principal>,
+}
@@ -0,0 +1,115 @@
+use crate::services::{index, scheduler};
+use crate::storage::{
+    quarantine_undecodable_records, ATTENDANCE, CHECK_IN_SESSIONS, ESCROW_DEPOSITS,
+    ESCROW_TRANSFERS, EVENTS, EVENT_INDEX, EVENT_INVITATIONS, EVENT_RATINGS, EVENT_REVIEWS,
+    EVENT_SERIES, INVITE_CODES, JOIN_REQUESTS, MATCH_RESULTS, NEXT_EVENT_ID, ORGANIZER_RATINGS,
+    PLAYER_STATS, QUARANTINE, RATING_HISTORY, REMINDER_OFFSETS, REMINDER_SCHEDULE, RESULT_SCHEDULE,
+    REVIEWS, REVIEWS_BY_USER, SCHEMA_VERSION, SERIES_EVENTS, SERIES_SCHEDULE, SKILL_RATINGS,
+    STATUS_SCHEDULE, TEAMMATE_REQUESTS, USER_ATTENDANCE,
+};
+use crate::types::rating::RatingSummary;
+use crate::types::review::Review;
+use crate::types::skill::SkillRating;
+
+// Bump this and add a step to `migrate` whenever a stored shape (e.g. `Event`) changes
+pub const CURRENT_SCHEMA_VERSION: u32 = 8;
+
+pub fn init() {
+    set_schema_version(CURRENT_SCHEMA_VERSION);
+}
+
+pub fn pre_upgrade() {
+    // All state is in stable structures, only make sure it is readable
+    validate_memory_layout();
+}
+
+pub fn post_upgrade() {
+    // Has to run before the typed maps are opened below
+    let quarantined = quarantine_undecodable_records();
+    if quarantined > 0 {
+        ic_cdk::println!("Quarantined {} undecodable records", quarantined);
+    }
+
+    validate_memory_layout();
+
+    let stored_version = SCHEMA_VERSION.with(|version| *version.borrow().get());
+    if stored_version > CURRENT_SCHEMA_VERSION {
+        ic_cdk::api::trap(format!(
+            "Cannot downgrade schema from version {} to {}",
+            stored_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    for version in stored_version..CURRENT_SCHEMA_VERSION {
+        migrate(version);
+    }
+    set_schema_version(CURRENT_SCHEMA_VERSION);
+
+    // Timers are cleared by the upgrade, re-arm the status scheduler
+    scheduler::resume();
+}
+
+// Migrate stored data from `version` to `version + 1`
+fn migrate(version: u32) {
+    match version {
+        // Deployments before schema versioning, the stored shapes are unchanged
+        0 => {}
+        // Records are now written in the versioned envelope (see the `versioned` crate),
+        // bare Candid records are still read as version 0
+        1 => {}
+        // Status transitions are now scheduled, build the schedule for existing events
+        2 => scheduler::backfill(),
+        // Reminders are now scheduled, build the reminder schedule for existing events
+        3 => scheduler::backfill_reminders(),
+        // Duplicate reviews are now detected through an (event, user) index
+        4 => Review::backfill_user_index(),
+        // Event and organizer rating aggregates are now maintained incrementally
+        5 => RatingSummary::backfill(),
+        // Event filters are now answered from an index, build it for existing events
+        6 => index::backfill(),
+        // Skill ratings are now seeded on join, seed the players of open events
+        7 => SkillRating::backfill(),
+        _ => ic_cdk::api::trap(format!("No migration from schema version {}", version)),
+    }
+}
+
+// Loading every stable structure checks its header, a mismatched
+// memory layout traps here and the upgrade is rolled back
+fn validate_memory_layout() {
+    EVENTS.with(|events| events.borrow().len());
+    REVIEWS.with(|reviews| reviews.borrow().len());
+    REVIEWS_BY_USER.with(|reviews_by_user| reviews_by_user.borrow().len());
+    EVENT_RATINGS.with(|ratings| ratings.borrow().len());
+    ORGANIZER_RATINGS.with(|ratings| ratings.borrow().len());
+    EVENT_REVIEWS.with(|event_reviews| event_reviews.borrow().len());
+    EVENT_INDEX.with(|index| index.borrow().len());
+    QUARANTINE.with(|quarantine| quarantine.borrow().len());
+    STATUS_SCHEDULE.with(|schedule| schedule.borrow().len());
+    REMINDER_SCHEDULE.with(|schedule| schedule.borrow().len());
+    EVENT_SERIES.with(|series| series.borrow().len());
+    SERIES_EVENTS.with(|series_events| series_events.borrow().len());
+    SERIES_SCHEDULE.with(|schedule| schedule.borrow().len());
+    ESCROW_DEPOSITS.with(|deposits| deposits.borrow().len());
+    ESCROW_TRANSFERS.with(|transfers| transfers.borrow().len());
+    CHECK_IN_SESSIONS.with(|sessions| sessions.borrow().len());
+    ATTENDANCE.with(|attendance| attendance.borrow().len());
+    USER_ATTENDANCE.with(|stats| stats.borrow().len());
+    EVENT_INVITATIONS.with(|invitations| invitations.borrow().len());
+    INVITE_CODES.with(|codes| codes.borrow().len());
+    JOIN_REQUESTS.with(|requests| requests.borrow().len());
+    TEAMMATE_REQUESTS.with(|requests| requests.borrow().len());
+    MATCH_RESULTS.with(|results| results.borrow().len());
+    RESULT_SCHEDULE.with(|schedule| schedule.borrow().len());
+    PLAYER_STATS.with(|stats| stats.borrow().len());
+    SKILL_RATINGS.with(|ratings| ratings.borrow().len());
+    RATING_HISTORY.with(|history| history.borrow().len());
+    REMINDER_OFFSETS.with(|offsets| offsets.borrow().get().0.len());
+    NEXT_EVENT_ID.with(|next_id| *next_id.borrow().get());
+    SCHEMA_VERSION.with(|version| *version.borrow().get());
+}
+
+fn set_schema_version(version: u32) {
+    SCHEMA_VERSION.with(|cell| {
+        cell.borrow_mut().set(version);
+    });
+}
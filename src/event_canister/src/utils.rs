@@ -1,8 +1,19 @@
 use crate::types::error::Error;
-use candid::Principal;
+use candid::{Nat, Principal};
+use ic_cdk::futures::spawn;
 use ic_cdk::management_canister::raw_rand;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
 
-use crate::types::{city::CityData, notification::NewNotification};
+use crate::types::{
+    city::CityData,
+    group_admin::GroupPermission,
+    notification::{NewNotification, NotificationDeliveryError},
+    skill::SportLevel,
+};
 
 pub async fn generate_unique_id() -> u64 {
     match raw_rand().await {
@@ -34,6 +45,142 @@ pub fn get_user_canister_id() -> Result<Principal, Error> {
     }
 }
 
+pub fn get_social_canister_id() -> Result<Principal, Error> {
+    match option_env!("CANISTER_ID_SOCIAL_CANISTER") {
+        Some(id) => Principal::from_text(id).map_err(|e| Error {
+            code: 400,
+            error: format!("Invalid canister ID: {}", e),
+            message: "Failed to parse social canister ID".into(),
+        }),
+        None => Err(Error {
+            code: 404,
+            error: "Social canister ID not found".into(),
+            message: "Social canister ID is not set in the environment".into(),
+        }),
+    }
+}
+
+pub fn get_ledger_canister_id() -> Result<Principal, Error> {
+    match option_env!("CANISTER_ID_ICRC1_LEDGER_CANISTER") {
+        Some(id) => Principal::from_text(id).map_err(|e| Error {
+            code: 400,
+            error: format!("Invalid canister ID: {}", e),
+            message: "Failed to parse ledger canister ID".into(),
+        }),
+        None => Err(Error {
+            code: 404,
+            error: "Ledger canister ID not found".into(),
+            message: "Ledger canister ID is not set in the environment".into(),
+        }),
+    }
+}
+
+pub fn get_owners_canister_id() -> Result<Principal, Error> {
+    match option_env!("CANISTER_ID_OWNERS_CANISTER") {
+        Some(id) => Principal::from_text(id).map_err(|e| Error {
+            code: 400,
+            error: format!("Invalid canister ID: {}", e),
+            message: "Failed to parse owners canister ID".into(),
+        }),
+        None => Err(Error {
+            code: 404,
+            error: "Owners canister ID not found".into(),
+            message: "Owners canister ID is not set in the environment".into(),
+        }),
+    }
+}
+
+fn ledger_error(error: String, message: &str) -> Error {
+    Error {
+        code: 502,
+        error,
+        message: message.into(),
+    }
+}
+
+fn block_index_to_u64(block_index: Nat) -> u64 {
+    block_index.0.to_u64_digits().first().copied().unwrap_or(0)
+}
+
+pub async fn ledger_fee() -> Result<u64, Error> {
+    let canister_id = get_ledger_canister_id()?;
+    let fee = ic_cdk::call::Call::unbounded_wait(canister_id, "icrc1_fee")
+        .await
+        .map_err(|e| ledger_error(format!("Ledger call error: {}", e), "Failed to get fee"))?
+        .candid::<Nat>()
+        .map_err(|e| {
+            ledger_error(
+                format!("Candid decoding failed: {}", e),
+                "Failed to get fee",
+            )
+        })?;
+    Ok(block_index_to_u64(fee))
+}
+
+// Pull `amount` from `from` into `to` with the allowance given to this canister,
+// returns the ledger block index
+pub async fn ledger_transfer_from(
+    from: Principal,
+    to: Account,
+    amount: u64,
+    memo: Vec<u8>,
+) -> Result<u64, Error> {
+    let canister_id = get_ledger_canister_id()?;
+    let args = TransferFromArgs {
+        spender_subaccount: None,
+        from: Account {
+            owner: from,
+            subaccount: None,
+        },
+        to,
+        amount: amount.into(),
+        fee: None,
+        memo: Some(memo.into()),
+        created_at_time: None,
+    };
+    ic_cdk::call::Call::unbounded_wait(canister_id, "icrc2_transfer_from")
+        .with_arg(args)
+        .await
+        .map_err(|e| ledger_error(format!("Ledger call error: {}", e), "Payment failed"))?
+        .candid::<Result<Nat, TransferFromError>>()
+        .map_err(|e| ledger_error(format!("Candid decoding failed: {}", e), "Payment failed"))?
+        .map(block_index_to_u64)
+        .map_err(|e| Error {
+            code: 402,
+            error: e.to_string(),
+            message: "Payment failed, approve the event canister to spend the event cost".into(),
+        })
+}
+
+// Send `amount` from a subaccount of this canister, returns the ledger block index
+pub async fn ledger_transfer(
+    from_subaccount: Subaccount,
+    to: Principal,
+    amount: u64,
+    memo: Vec<u8>,
+) -> Result<u64, Error> {
+    let canister_id = get_ledger_canister_id()?;
+    let args = TransferArg {
+        from_subaccount: Some(from_subaccount),
+        to: Account {
+            owner: to,
+            subaccount: None,
+        },
+        fee: None,
+        created_at_time: None,
+        memo: Some(memo.into()),
+        amount: amount.into(),
+    };
+    ic_cdk::call::Call::unbounded_wait(canister_id, "icrc1_transfer")
+        .with_arg(args)
+        .await
+        .map_err(|e| ledger_error(format!("Ledger call error: {}", e), "Transfer failed"))?
+        .candid::<Result<Nat, TransferError>>()
+        .map_err(|e| ledger_error(format!("Candid decoding failed: {}", e), "Transfer failed"))?
+        .map(block_index_to_u64)
+        .map_err(|e| ledger_error(e.to_string(), "Transfer failed"))
+}
+
 pub async fn get_city(city_id: u16, governorate_id: u8) -> Result<CityData, Error> {
     let canister_id = get_user_canister_id()?;
     let call_results = ic_cdk::call::Call::unbounded_wait(canister_id, "get_city")
@@ -48,15 +195,163 @@ pub async fn get_city(city_id: u16, governorate_id: u8) -> Result<CityData, Erro
     }
 }
 
+pub async fn get_user_free_days(user_id: Principal) -> Result<Option<Vec<u8>>, Error> {
+    let canister_id = get_user_canister_id()?;
+    ic_cdk::call::Call::unbounded_wait(canister_id, "get_user_free_days")
+        .with_arg(user_id)
+        .await
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("User canister call error: {}", e),
+            message: "Failed to get free days".into(),
+        })?
+        .candid::<Result<Option<Vec<u8>>, Error>>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to get free days".into(),
+        })?
+}
+
+pub async fn get_user_skill_levels(user_id: Principal) -> Result<Vec<SportLevel>, Error> {
+    let canister_id = get_user_canister_id()?;
+    ic_cdk::call::Call::unbounded_wait(canister_id, "get_user_skill_levels")
+        .with_arg(user_id)
+        .await
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("User canister call error: {}", e),
+            message: "Failed to get skill levels".into(),
+        })?
+        .candid::<Result<Vec<SportLevel>, Error>>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to get skill levels".into(),
+        })?
+}
+
+pub async fn get_member_group_ids(user_id: Principal) -> Result<Vec<String>, Error> {
+    let canister_id = get_social_canister_id()?;
+    ic_cdk::call::Call::unbounded_wait(canister_id, "get_member_group_ids")
+        .with_arg(user_id)
+        .await
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Social canister call error: {}", e),
+            message: "Failed to get group memberships".into(),
+        })?
+        .candid::<Vec<String>>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to get group memberships".into(),
+        })
+}
+
+// Numeric group ID the owners canister keeps the admins of a social canister
+// group under, same as its `get_group_admin_id`
+pub fn group_admin_id(group_id: &str) -> u64 {
+    let hash = Sha256::digest(group_id.as_bytes());
+    u64::from_be_bytes(hash[..8].try_into().expect("SHA-256 is 32 bytes"))
+}
+
+pub async fn is_group_admin(
+    group_id: &str,
+    user_id: Principal,
+    permission: GroupPermission,
+) -> Result<bool, Error> {
+    let canister_id = get_owners_canister_id()?;
+    ic_cdk::call::Call::unbounded_wait(canister_id, "is_group_admin")
+        .with_args(&(group_admin_id(group_id), user_id, Some(permission)))
+        .await
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Owners canister call error: {}", e),
+            message: "Failed to check group admins".into(),
+        })?
+        .candid::<bool>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to check group admins".into(),
+        })
+}
+
+// Lowercase text for keyword search. Arabic diacritics and tatweel are
+// dropped and letters that are written interchangeably are unified
+// (alef variants, alef maqsura and yeh, teh marbuta and heh)
+pub fn normalize_search_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}'))
+        .map(|c| match c {
+            'أ' | 'إ' | 'آ' | 'ٱ' => 'ا',
+            'ى' => 'ي',
+            'ة' => 'ه',
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
 pub async fn add_notification(
     user_id: Principal,
     notification: NewNotification,
 ) -> Result<(), Error> {
     let canister_id = get_user_canister_id()?;
-    ic_cdk::call::Call::unbounded_wait(canister_id, "add_notification")
+    ic_cdk::call::Call::unbounded_wait(canister_id, "deliver_notification")
         .with_args(&(user_id, notification))
         .await
-        .expect("User canister call error")
-        .candid::<Result<(), Error>>()
-        .expect("Candid decoding failed")
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("User canister call error: {}", e),
+            message: "Failed to add notification".into(),
+        })?
+        .candid::<Result<(), NotificationDeliveryError>>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to add notification".into(),
+        })?
+        .map_err(Error::from)
+}
+
+pub async fn add_notifications(
+    deliveries: Vec<(Principal, NewNotification)>,
+) -> Result<Vec<Result<(), NotificationDeliveryError>>, Error> {
+    let canister_id = get_user_canister_id()?;
+    ic_cdk::call::Call::unbounded_wait(canister_id, "deliver_notifications")
+        .with_arg(&deliveries)
+        .await
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("User canister call error: {}", e),
+            message: "Failed to add notifications".into(),
+        })?
+        .candid::<Result<Vec<Result<(), NotificationDeliveryError>>, NotificationDeliveryError>>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to add notifications".into(),
+        })?
+        .map_err(Error::from)
+}
+
+// Send the same notification to every recipient from a timer, so the caller
+// does not wait for the user canister (delivery errors are ignored)
+pub fn notify_in_background(recipients: Vec<Principal>, notification: NewNotification) {
+    if recipients.is_empty() {
+        return;
+    }
+
+    let deliveries = recipients
+        .into_iter()
+        .map(|recipient| (recipient, notification.clone()))
+        .collect();
+
+    ic_cdk_timers::set_timer(Duration::from_nanos(1), move || {
+        spawn(async move {
+            let _ = add_notifications(deliveries).await;
+        });
+    });
 }
@@ -59,8 +59,18 @@ pub fn update_group_admin_permissions(
 
 }
 
-// Check if a principal is a group admin
+// Check if a principal is a group admin, with the given permission when one is passed
 #[query]
-pub fn is_group_admin(group_id: u64, principal: Principal) -> bool {
-    services::group_admin::is_group_admin(group_id, &principal)
+pub fn is_group_admin(
+    group_id: u64,
+    principal: Principal,
+    permission: Option<GroupPermission>,
+) -> bool {
+    services::group_admin::is_group_admin(group_id, &principal, permission.as_ref())
+}
+
+// Group ID to add admins for a social canister group
+#[query]
+pub fn get_group_admin_id(social_group_id: String) -> u64 {
+    services::group_admin::group_admin_id(&social_group_id)
 }
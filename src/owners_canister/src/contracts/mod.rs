@@ -2,6 +2,7 @@ pub mod admin_request;
 pub mod group_admins;
 pub mod middleware;
 pub mod owners;
+pub mod quarantine;
 
 pub use admin_request::*;
 pub use group_admins::*;
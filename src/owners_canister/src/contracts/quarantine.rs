@@ -0,0 +1,16 @@
+use crate::storage;
+use crate::types::{Error, QuarantinedRecord};
+use ic_cdk::query;
+
+// Get records that failed to decode during an upgrade (only controllers can see this)
+#[query]
+pub fn get_quarantined_records() -> Result<Vec<QuarantinedRecord>, Error> {
+    if !ic_cdk::api::is_controller(&ic_cdk::api::msg_caller()) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only controllers can read quarantined records".to_string(),
+        });
+    }
+    Ok(storage::get_quarantined_records())
+}
@@ -2,20 +2,32 @@ mod contracts;
 mod services;
 mod storage;
 mod types;
+mod upgrade;
 
 // Re-export public API endpoints from contracts
 pub use contracts::*;
 
 use candid::Principal;
-use ic_cdk::init;
+use ic_cdk::{init, post_upgrade, pre_upgrade};
 use types::{
     AdminRequest, CreateAdminRequest, Error, GroupAdmin, GroupPermission, Owner, OwnerRole,
-    Permission, ProcessAdminRequest,
+    Permission, ProcessAdminRequest, QuarantinedRecord,
 };
 
 #[init]
 fn init() {
     services::owner::initialize_canister();
+    upgrade::init();
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    upgrade::pre_upgrade();
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    upgrade::post_upgrade();
 }
 
 ic_cdk::export_candid!();
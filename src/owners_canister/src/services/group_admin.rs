@@ -2,6 +2,7 @@ use crate::services::owner;
 use crate::storage;
 use crate::types::{Error, GroupAdmin, GroupPermission};
 use candid::Principal;
+use sha2::{Digest, Sha256};
 
 // Helper function to get current timestamp
 fn current_timestamp() -> u64 {
@@ -144,7 +145,21 @@ pub fn update_group_admin_permissions(
     Ok(())
 }
 
-// Check if a principal is a group admin
-pub fn is_group_admin(group_id: u64, principal: &Principal) -> bool {
-    storage::group_admin_exists(group_id, principal)
+// Check if a principal is a group admin, optionally holding a specific permission
+pub fn is_group_admin(
+    group_id: u64,
+    principal: &Principal,
+    permission: Option<&GroupPermission>,
+) -> bool {
+    match permission {
+        Some(permission) => has_group_permission(principal, group_id, permission),
+        None => storage::group_admin_exists(group_id, principal),
+    }
+}
+
+// Numeric ID used for the admins of a social canister group: the first
+// 8 bytes of the SHA-256 of its text ID
+pub fn group_admin_id(social_group_id: &str) -> u64 {
+    let hash = Sha256::digest(social_group_id.as_bytes());
+    u64::from_be_bytes(hash[..8].try_into().unwrap())
 }
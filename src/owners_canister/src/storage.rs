@@ -1,56 +1,87 @@
-use crate::types::{AdminRequest, GroupAdmin, Owner};
+use crate::types::{AdminRequest, GroupAdmin, Owner, QuarantinedRecord};
 use candid::Principal;
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
 use ic_stable_structures::storable::Bound;
-use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, Storable};
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell, Storable};
 use std::borrow::Cow;
 use std::cell::RefCell;
+use versioned::{Format, Versioned};
 
 // Type alias for virtual memory
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 
+impl Versioned for Owner {
+    const VERSION: u8 = 1;
+    const FORMAT: Format = Format::Json;
+}
+
 // Implementing Storable trait for Owner
 impl Storable for Owner {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        let json = serde_json::to_string(self).unwrap();
-        Cow::Owned(json.into_bytes())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn into_bytes(self) -> Vec<u8> {
-        let json = serde_json::to_string(&self).unwrap();
-        json.into_bytes()
+        versioned::encode(&self)
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        let json = String::from_utf8(bytes.to_vec()).unwrap();
-        serde_json::from_str(&json).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 }
 
+impl Versioned for GroupAdmin {
+    const VERSION: u8 = 1;
+    const FORMAT: Format = Format::Json;
+}
+
 // Implementing Storable trait for GroupAdmin
 impl Storable for GroupAdmin {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        let json = serde_json::to_string(self).unwrap();
-        Cow::Owned(json.into_bytes())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn into_bytes(self) -> Vec<u8> {
-        let json = serde_json::to_string(&self).unwrap();
-        json.into_bytes()
+        versioned::encode(&self)
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        let json = String::from_utf8(bytes.to_vec()).unwrap();
-        serde_json::from_str(&json).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 }
 
+impl Versioned for AdminRequest {
+    const VERSION: u8 = 1;
+    const FORMAT: Format = Format::Json;
+}
+
 // Implementing Storable trait for AdminRequest
 impl Storable for AdminRequest {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Wrapper for the optional super admin principal stored in a StableCell
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct SuperAdmin(pub Option<Principal>);
+
+// Implementing Storable trait for SuperAdmin
+impl Storable for SuperAdmin {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
         let json = serde_json::to_string(self).unwrap();
         Cow::Owned(json.into_bytes())
@@ -69,6 +100,28 @@ impl Storable for AdminRequest {
     const BOUND: Bound = Bound::Unbounded;
 }
 
+impl Versioned for QuarantinedRecord {
+    const VERSION: u8 = 1;
+    const FORMAT: Format = Format::Json;
+}
+
+// Implementing Storable trait for QuarantinedRecord
+impl Storable for QuarantinedRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
 // Thread-local storage for managing canister state
 thread_local! {
     // Memory manager for handling stable storage
@@ -104,7 +157,27 @@ thread_local! {
     );
 
     // Store the initial super admin principal
-    static SUPER_ADMIN: RefCell<Option<Principal>> = RefCell::new(None);
+    static SUPER_ADMIN: RefCell<StableCell<SuperAdmin, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(54))), // Memory ID 54 for Super Admin
+            SuperAdmin::default(),
+        )
+    );
+
+    // Version of the stored data shapes, see `upgrade.rs`
+    static SCHEMA_VERSION: RefCell<StableCell<u32, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(55))), // Memory ID 55 for Schema Version
+            0,
+        )
+    );
+
+    // Records that failed to decode during an upgrade
+    static QUARANTINE: RefCell<StableBTreeMap<u64, QuarantinedRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(56))), // Memory ID 56 for Quarantine
+        )
+    );
 }
 
 // Storage functions for owners
@@ -190,12 +263,27 @@ pub fn group_admin_exists(group_id: u64, principal: &Principal) -> bool {
 // Super admin functions
 pub fn set_super_admin(principal: Principal) {
     SUPER_ADMIN.with(|admin| {
-        *admin.borrow_mut() = Some(principal);
+        admin.borrow_mut().set(SuperAdmin(Some(principal)));
     });
 }
 
 pub fn get_super_admin() -> Option<Principal> {
-    SUPER_ADMIN.with(|admin| *admin.borrow())
+    SUPER_ADMIN.with(|admin| admin.borrow().get().0)
+}
+
+// The initial super admin is the only self-created SuperAdmin owner
+pub fn find_initial_super_admin() -> Option<Principal> {
+    OWNERS.with(|owners| {
+        owners.borrow().iter().find_map(|entry| {
+            let owner = entry.value();
+            if owner.role == crate::types::OwnerRole::SuperAdmin && owner.created_by == owner.principal
+            {
+                Some(owner.principal)
+            } else {
+                None
+            }
+        })
+    })
 }
 
 pub fn count_super_admins() -> usize {
@@ -276,4 +364,107 @@ pub fn mark_principal_requested(principal: Principal) {
 
 pub fn unmark_principal_requested(principal: &Principal) -> bool {
     REQUEST_PRINCIPALS.with(|principals| principals.borrow_mut().remove(principal).is_some())
-}
\ No newline at end of file
+}
+
+// Schema version functions
+pub fn get_schema_version() -> u32 {
+    SCHEMA_VERSION.with(|version| *version.borrow().get())
+}
+
+pub fn set_schema_version(version: u32) {
+    SCHEMA_VERSION.with(|cell| {
+        cell.borrow_mut().set(version);
+    });
+}
+
+// Loading every stable structure checks its header, a mismatched
+// memory layout traps here and the upgrade is rolled back
+pub fn validate_memory_layout() {
+    OWNERS.with(|owners| owners.borrow().len());
+    GROUP_ADMINS.with(|admins| admins.borrow().len());
+    ADMIN_REQUESTS.with(|requests| requests.borrow().len());
+    REQUEST_PRINCIPALS.with(|principals| principals.borrow().len());
+    SUPER_ADMIN.with(|admin| admin.borrow().get().0);
+    SCHEMA_VERSION.with(|version| *version.borrow().get());
+    QUARANTINE.with(|quarantine| quarantine.borrow().len());
+}
+
+// Quarantine functions
+
+// Move records that no longer decode into QUARANTINE, returns how many were moved.
+// Must run before OWNERS, GROUP_ADMINS and ADMIN_REQUESTS are first used
+pub fn quarantine_undecodable_records() -> usize {
+    let memory = |id: u8| MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(id)));
+
+    let records = [
+        versioned::quarantine_undecodable::<Principal, Owner, _>("owners", memory(50)),
+        versioned::quarantine_undecodable::<String, GroupAdmin, _>("group_admins", memory(51)),
+        versioned::quarantine_undecodable::<String, AdminRequest, _>("admin_requests", memory(52)),
+    ]
+    .concat();
+
+    let count = records.len();
+    let quarantined_at = ic_cdk::api::time();
+    QUARANTINE.with(|quarantine| {
+        let mut quarantine = quarantine.borrow_mut();
+        for record in records {
+            let id = quarantine.last_key_value().map_or(0, |(id, _)| id + 1);
+            quarantine.insert(
+                id,
+                QuarantinedRecord {
+                    store: record.store,
+                    key: record.key,
+                    bytes: record.bytes,
+                    error: record.error,
+                    quarantined_at,
+                },
+            );
+        }
+    });
+    count
+}
+
+pub fn get_quarantined_records() -> Vec<QuarantinedRecord> {
+    QUARANTINE.with(|quarantine| quarantine.borrow().values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OwnerRole, Permission};
+
+    fn owner() -> Owner {
+        Owner {
+            principal: Principal::from_slice(&[1; 29]),
+            name: "Owner".to_string(),
+            role: OwnerRole::Admin,
+            created_at: 1,
+            created_by: Principal::from_slice(&[2; 29]),
+            permissions: vec![Permission::ManageGroups],
+        }
+    }
+
+    #[test]
+    fn reads_bare_json_owner() {
+        // Written before the versioned envelope, a plain JSON object
+        let bytes = serde_json::to_vec(&owner()).unwrap();
+        assert_eq!(bytes[0], b'{');
+
+        let decoded = Owner::from_bytes(Cow::Owned(bytes));
+        assert!(decoded.principal == owner().principal);
+        assert_eq!(decoded.name, "Owner");
+        assert!(decoded.role == OwnerRole::Admin);
+        assert_eq!(decoded.permissions, vec![Permission::ManageGroups]);
+    }
+
+    #[test]
+    fn round_trips_versioned_owner() {
+        let bytes = owner().to_bytes().into_owned();
+        assert_eq!(bytes[0], Owner::VERSION);
+        assert_eq!(bytes[1], b'{');
+
+        let decoded = Owner::from_bytes(Cow::Owned(bytes));
+        assert_eq!(decoded.name, "Owner");
+        assert_eq!(decoded.created_by, Principal::from_slice(&[2; 29]));
+    }
+}
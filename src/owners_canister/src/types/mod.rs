@@ -2,8 +2,10 @@ pub mod admin_request;
 pub mod error;
 pub mod group_admin;
 pub mod owner;
+pub mod quarantine;
 
 pub use admin_request::*;
 pub use error::*;
 pub use group_admin::*;
 pub use owner::*;
+pub use quarantine::*;
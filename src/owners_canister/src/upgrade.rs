@@ -0,0 +1,55 @@
+use crate::storage;
+
+// Bump this and add a step to `migrate` whenever a stored shape (e.g. `Owner`) changes
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+pub fn init() {
+    storage::set_schema_version(CURRENT_SCHEMA_VERSION);
+}
+
+pub fn pre_upgrade() {
+    // All state is in stable structures, only make sure it is readable
+    storage::validate_memory_layout();
+}
+
+pub fn post_upgrade() {
+    // Has to run before the typed maps are opened below
+    let quarantined = storage::quarantine_undecodable_records();
+    if quarantined > 0 {
+        ic_cdk::println!("Quarantined {} undecodable records", quarantined);
+    }
+
+    storage::validate_memory_layout();
+
+    let stored_version = storage::get_schema_version();
+    if stored_version > CURRENT_SCHEMA_VERSION {
+        ic_cdk::api::trap(format!(
+            "Cannot downgrade schema from version {} to {}",
+            stored_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    for version in stored_version..CURRENT_SCHEMA_VERSION {
+        migrate(version);
+    }
+    storage::set_schema_version(CURRENT_SCHEMA_VERSION);
+}
+
+// Migrate stored data from `version` to `version + 1`
+fn migrate(version: u32) {
+    match version {
+        // The super admin used to live on the heap and was lost on upgrade,
+        // restore it from the self-created SuperAdmin owner record
+        0 => {
+            if storage::get_super_admin().is_none() {
+                if let Some(principal) = storage::find_initial_super_admin() {
+                    storage::set_super_admin(principal);
+                }
+            }
+        }
+        // Records are now written in the versioned envelope (see the `versioned` crate),
+        // bare JSON records are still read as version 0
+        1 => {}
+        _ => ic_cdk::api::trap(format!("No migration from schema version {}", version)),
+    }
+}
@@ -0,0 +1,35 @@
+use crate::types::comments::{Comments, NewComment, UpdateComment};
+use crate::types::error::Error;
+use ic_cdk;
+use ic_cdk::{query, update};
+
+#[update]
+pub async fn create_comment(new_comment: NewComment) -> Result<Comments, Error> {
+    Comments::new(new_comment).await
+}
+
+#[update]
+pub async fn reply_to_comment(
+    parent_comment_id: String,
+    new_comment: NewComment,
+) -> Result<Comments, Error> {
+    Comments::reply(&parent_comment_id, new_comment).await
+}
+
+#[update]
+pub fn update_comment(
+    comment_id: String,
+    updated_comment: UpdateComment,
+) -> Result<Comments, Error> {
+    Comments::update(&comment_id, updated_comment)
+}
+
+#[update]
+pub fn delete_comment(comment_id: String) -> Result<(), Error> {
+    Comments::delete(&comment_id)
+}
+
+#[query]
+pub fn get_post_comments(post_id: String) -> Result<Vec<Comments>, Error> {
+    Comments::get_post_comments(&post_id)
+}
@@ -62,6 +62,11 @@ pub fn get_member_groups(user: Principal) -> Vec<Group> {
     Group::get_member_groups(user)
 }
 
+#[query]
+pub fn get_member_group_ids(user: Principal) -> Vec<String> {
+    Group::get_member_group_ids(user)
+}
+
 #[query]
 pub fn get_group_posts(group_id: &str) -> Vec<Post> {
     match Group::get_by_id(group_id) {
@@ -24,6 +24,7 @@ fn is_update_method(method_name: &str) -> bool {
     // * Check if method follows update patterns
     let update_patterns = [
         "set_", "update_", "create_", "delete_", "add_", "remove_", "join_", "leave_", "save_",
+        "reply_", "rsvp_",
     ];
 
     update_patterns
@@ -1,4 +1,7 @@
 pub mod middleware;
 pub mod group;
 pub mod post;
+pub mod comment;
 pub mod like;
+pub mod tal3a;
+pub mod quarantine;
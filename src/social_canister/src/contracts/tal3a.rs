@@ -0,0 +1,38 @@
+use crate::types::error::Error;
+use crate::types::tal3a::{NewTal3a, Tal3a};
+use crate::types::tal3a_members::{JoinStatus, Tal3aMember};
+use ic_cdk;
+use ic_cdk::api::msg_caller;
+use ic_cdk::{query, update};
+
+#[update]
+pub async fn create_tal3a(new_tal3a: NewTal3a) -> Result<Tal3a, Error> {
+    Tal3a::new(new_tal3a).await
+}
+
+#[query]
+pub fn get_tal3a(tal3a_id: String) -> Result<Tal3a, Error> {
+    Tal3a::get_for_member(&tal3a_id, msg_caller())
+}
+
+#[update]
+pub fn rsvp_tal3a(tal3a_id: String, status: JoinStatus) -> Result<Tal3aMember, Error> {
+    let user_id = msg_caller();
+    Tal3a::get_by_id(&tal3a_id)?.rsvp(user_id, status)
+}
+
+#[query]
+pub fn get_tal3a_members(tal3a_id: String) -> Result<Vec<Tal3aMember>, Error> {
+    Ok(Tal3a::get_for_member(&tal3a_id, msg_caller())?.get_members())
+}
+
+#[query]
+pub fn get_group_tal3as(group_id: String) -> Vec<Tal3a> {
+    Tal3a::get_group_tal3as(&group_id)
+}
+
+#[query]
+pub fn get_my_tal3as() -> Vec<Tal3a> {
+    let user_id = msg_caller();
+    Tal3a::get_user_tal3as(user_id)
+}
@@ -2,17 +2,38 @@ pub mod contracts;
 pub mod services;
 pub mod storage;
 pub mod types;
+pub mod upgrade;
 pub mod utils;
 
 use candid::Principal;
 use ic_cdk;
+use ic_cdk::{init, post_upgrade, pre_upgrade};
+
+#[init]
+fn init() {
+    upgrade::init();
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    upgrade::pre_upgrade();
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    upgrade::post_upgrade();
+}
 
 use crate::types::{
+    comments::{Comments, NewComment, UpdateComment},
     error::Error,
     group::{CreatingGroup, Group, GroupFilter},
     group_members::GroupMember,
     likes::{Like, LikeTarget},
     posts::{NewPost, Post, UpdatePost},
+    quarantine::QuarantinedRecord,
+    tal3a::{NewTal3a, Tal3a},
+    tal3a_members::{JoinStatus, Tal3aMember},
 };
 
 // * export contracts
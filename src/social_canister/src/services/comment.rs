@@ -0,0 +1,354 @@
+use crate::types::error::Error;
+use candid::Principal;
+use ic_cdk::{api::msg_caller, futures::spawn};
+
+use crate::{
+    storage::{StringVec, COMMENTS, COMMENTS_BY_POST, COMMENTS_BY_USER, POSTS},
+    types::{
+        comments::{Comments, NewComment, UpdateComment},
+        group::Group,
+        notification::{NewNotification, NotificationType},
+        posts::Post,
+    },
+    utils::{add_notifications, generate_unique_id},
+};
+
+// * top level comments are level 0, replies can go down to this level
+pub const MAX_COMMENT_LEVEL: u8 = 3;
+
+impl Comments {
+    pub async fn new(comment: NewComment) -> Result<Comments, Error> {
+        Self::create(comment, None).await
+    }
+
+    pub async fn reply(parent_comment_id: &str, comment: NewComment) -> Result<Comments, Error> {
+        let parent = Self::get_by_id(parent_comment_id)?;
+
+        if parent.post_id != comment.post_id {
+            return Err(Error {
+                code: 400,
+                error: "Bad Request".into(),
+                message: "Parent comment does not belong to the given post".to_string(),
+            });
+        }
+
+        if parent.level >= MAX_COMMENT_LEVEL {
+            return Err(Error {
+                code: 400,
+                error: "Maximum reply depth reached".into(),
+                message: format!(
+                    "Replies cannot be nested deeper than {} levels",
+                    MAX_COMMENT_LEVEL
+                ),
+            });
+        }
+
+        Self::create(comment, Some(parent)).await
+    }
+
+    async fn create(comment: NewComment, parent: Option<Comments>) -> Result<Comments, Error> {
+        let user_id = msg_caller();
+
+        if comment.content.trim().is_empty() && comment.image.is_none() {
+            return Err(Error {
+                code: 400,
+                error: "Bad Request".into(),
+                message: "Comment must have content or an image".to_string(),
+            });
+        }
+
+        // * check the post exists and the user is a member of its group
+        let post = Self::check_membership(&comment.post_id, user_id)?;
+
+        let comment_id = generate_unique_id().await.map_err(|_| Error {
+            code: 500,
+            error: "Failed to generate unique comment ID".into(),
+            message: "Failed to generate unique comment ID".to_string(),
+        })?;
+        let created_at = ic_cdk::api::time();
+
+        // * path keys sort replies right after their parent, oldest first
+        let own_key = format!("{:020}-{}", created_at, comment_id);
+        let (level, path_key, parent_comment_id) = match &parent {
+            Some(parent) => (
+                parent.level + 1,
+                format!("{}/{}", parent.path_key, own_key),
+                Some(parent.comment_id.clone()),
+            ),
+            None => (0, own_key, None),
+        };
+
+        let new_comment = Comments {
+            group_id: post.group_id.clone(),
+            post_id: post.post_id.clone(),
+            author: user_id,
+            comment_id,
+            parent_comment_id,
+            level,
+            path_key,
+            content: comment.content,
+            image: comment.image.unwrap_or_default(),
+            created_at,
+            updated_at: created_at,
+        };
+
+        COMMENTS.with(|comments| {
+            comments
+                .borrow_mut()
+                .insert(new_comment.comment_id.clone(), new_comment.clone());
+        });
+
+        // * add comment in COMMENTS_BY_POST , COMMENTS_BY_USER for fast indexing
+        COMMENTS_BY_POST.with(|comments_by_post| {
+            let mut post_comments = comments_by_post
+                .borrow()
+                .get(&new_comment.post_id)
+                .unwrap_or(StringVec(Vec::new()));
+            post_comments.0.push(new_comment.comment_id.clone());
+            comments_by_post
+                .borrow_mut()
+                .insert(new_comment.post_id.clone(), post_comments);
+        });
+
+        COMMENTS_BY_USER.with(|comments_by_user| {
+            let mut user_comments = comments_by_user
+                .borrow()
+                .get(&user_id)
+                .unwrap_or(StringVec(Vec::new()));
+            user_comments.0.push(new_comment.comment_id.clone());
+            comments_by_user.borrow_mut().insert(user_id, user_comments);
+        });
+
+        // * increment post comments counter
+        POSTS.with(|posts| {
+            let mut posts = posts.borrow_mut();
+            if let Some(mut post) = posts.get(&new_comment.post_id) {
+                post.comments += 1;
+                posts.insert(new_comment.post_id.clone(), post);
+            }
+        });
+
+        // * notify the post author and the replied-to author
+        let mut recipients = vec![post.author];
+        if let Some(parent) = &parent {
+            if !recipients.contains(&parent.author) {
+                recipients.push(parent.author);
+            }
+        }
+        recipients.retain(|recipient| *recipient != user_id);
+
+        if !recipients.is_empty() {
+            let content = if parent.is_some() {
+                "Someone replied to your comment".to_string()
+            } else {
+                "Someone commented on your post".to_string()
+            };
+            ic_cdk_timers::set_timer(std::time::Duration::from_nanos(1), move || {
+                let recipients = recipients.clone();
+                let content = content.clone();
+                spawn(async move {
+                    let deliveries = recipients
+                        .into_iter()
+                        .map(|recipient| {
+                            (
+                                recipient,
+                                NewNotification {
+                                    content: content.clone(),
+                                    notification_type: NotificationType::Message,
+                                },
+                            )
+                        })
+                        .collect();
+                    // Send notifications in one batch (ignore errors)
+                    let _ = add_notifications(deliveries).await;
+                });
+            });
+        }
+
+        Ok(new_comment)
+    }
+
+    pub fn get_by_id(comment_id: &str) -> Result<Comments, Error> {
+        COMMENTS.with(|comments| {
+            comments
+                .borrow()
+                .get(&comment_id.to_string())
+                .ok_or_else(|| Error {
+                    code: 404,
+                    error: "Comment not found".into(),
+                    message: "No comment exists with the given ID".to_string(),
+                })
+        })
+    }
+
+    pub fn update(comment_id: &str, updated_comment: UpdateComment) -> Result<Comments, Error> {
+        let mut comment = Self::get_by_id(comment_id)?;
+
+        // * check if author is the same
+        if comment.author != msg_caller() {
+            return Err(Error {
+                code: 403,
+                error: "Unauthorized".into(),
+                message: "Only comment author can update the comment".to_string(),
+            });
+        }
+
+        if updated_comment.content.is_none() && updated_comment.image.is_none() {
+            return Err(Error {
+                code: 400,
+                error: "Bad Request".into(),
+                message: "No fields to update".to_string(),
+            });
+        }
+
+        if let Some(content) = updated_comment.content {
+            comment.content = content;
+        }
+
+        if let Some(image) = updated_comment.image {
+            comment.image = image;
+        }
+
+        if comment.content.trim().is_empty() && comment.image.is_empty() {
+            return Err(Error {
+                code: 400,
+                error: "Bad Request".into(),
+                message: "Comment must have content or an image".to_string(),
+            });
+        }
+
+        comment.updated_at = ic_cdk::api::time();
+
+        COMMENTS.with(|comments| {
+            comments
+                .borrow_mut()
+                .insert(comment_id.to_string(), comment.clone());
+        });
+
+        Ok(comment)
+    }
+
+    pub fn delete(comment_id: &str) -> Result<(), Error> {
+        let comment = Self::get_by_id(comment_id)?;
+
+        if comment.author != msg_caller() {
+            return Err(Error {
+                code: 403,
+                error: "Unauthorized".into(),
+                message: "Only comment author can delete the comment".to_string(),
+            });
+        }
+
+        // * deleting a comment removes its whole reply thread
+        let reply_prefix = format!("{}/", comment.path_key);
+        let removed: Vec<Comments> = Self::get_for_post(&comment.post_id)
+            .into_iter()
+            .filter(|c| c.comment_id == comment.comment_id || c.path_key.starts_with(&reply_prefix))
+            .collect();
+
+        Self::remove_all(&comment.post_id, &removed);
+
+        Ok(())
+    }
+
+    pub fn get_post_comments(post_id: &str) -> Result<Vec<Comments>, Error> {
+        Self::check_membership(post_id, msg_caller())?;
+        Ok(Self::get_for_post(post_id))
+    }
+
+    // * remove every comment of a post, used when the post is deleted
+    pub fn delete_post_comments(post_id: &str) {
+        let removed = Self::get_for_post(post_id);
+        Self::remove_all(post_id, &removed);
+    }
+
+    // * all comments of a post ordered by thread (path_key)
+    fn get_for_post(post_id: &str) -> Vec<Comments> {
+        let comment_ids = COMMENTS_BY_POST.with(|comments_by_post| {
+            comments_by_post
+                .borrow()
+                .get(&post_id.to_string())
+                .map(|ids| ids.0)
+                .unwrap_or_default()
+        });
+
+        let mut comments: Vec<Comments> = comment_ids
+            .iter()
+            .filter_map(|id| Self::get_by_id(id).ok())
+            .collect();
+        comments.sort_by(|a, b| a.path_key.cmp(&b.path_key));
+        comments
+    }
+
+    fn remove_all(post_id: &str, removed: &[Comments]) {
+        if removed.is_empty() {
+            return;
+        }
+
+        COMMENTS.with(|comments| {
+            let mut comments = comments.borrow_mut();
+            for comment in removed {
+                comments.remove(&comment.comment_id);
+            }
+        });
+
+        COMMENTS_BY_POST.with(|comments_by_post| {
+            let mut comments_by_post = comments_by_post.borrow_mut();
+            if let Some(mut comment_ids) = comments_by_post.get(&post_id.to_string()) {
+                comment_ids
+                    .0
+                    .retain(|id| !removed.iter().any(|c| &c.comment_id == id));
+                comments_by_post.insert(post_id.to_string(), comment_ids);
+            }
+        });
+
+        COMMENTS_BY_USER.with(|comments_by_user| {
+            let mut comments_by_user = comments_by_user.borrow_mut();
+            for comment in removed {
+                if let Some(mut comment_ids) = comments_by_user.get(&comment.author) {
+                    comment_ids.0.retain(|id| id != &comment.comment_id);
+                    comments_by_user.insert(comment.author, comment_ids);
+                }
+            }
+        });
+
+        // * decrement post comments counter
+        POSTS.with(|posts| {
+            let mut posts = posts.borrow_mut();
+            if let Some(mut post) = posts.get(&post_id.to_string()) {
+                post.comments = post.comments.saturating_sub(removed.len() as u64);
+                posts.insert(post_id.to_string(), post);
+            }
+        });
+    }
+
+    fn check_membership(post_id: &str, user_id: Principal) -> Result<Post, Error> {
+        // * check if the post exists
+        let post = Post::get_by_id(post_id).map_err(|_| Error {
+            code: 404,
+            error: "cannot access comments of unknown post".into(),
+            message: "No post exists with the given ID".to_string(),
+        })?;
+
+        // * check if the post belongs to a group and user in this group
+        let post_group = Group::get_by_id(&post.group_id).map_err(|_| Error {
+            code: 404,
+            error: "cannot access comments of post from unknown group".into(),
+            message: "No group exists with the given ID".to_string(),
+        })?;
+
+        if !post_group
+            .get_group_members()
+            .iter()
+            .any(|m| m.user_id == user_id)
+        {
+            return Err(Error {
+                code: 403,
+                error: "cannot access comments of post from group you are not a member of".into(),
+                message: "User is not a member of the group".to_string(),
+            });
+        }
+
+        Ok(post)
+    }
+}
@@ -360,6 +360,17 @@ impl Group {
         })
     }
 
+    // * ids only, for sibling canisters checking membership
+    pub fn get_member_group_ids(user: Principal) -> Vec<String> {
+        GROUPS_BY_USER.with(|user_groups| {
+            user_groups
+                .borrow()
+                .get(&user)
+                .map(|groups| groups.0)
+                .unwrap_or_default()
+        })
+    }
+
     pub fn delete(&self) -> Result<(), Error> {
         self.delete_internal(None)
     }
@@ -1,7 +1,7 @@
 pub mod group;
 pub mod post;
-// pub mod comment;
+pub mod comment;
 pub mod like;
-// pub mod tal3a;
+pub mod tal3a;
 // pub mod group_members;
 // pub mod tal3a_members;
\ No newline at end of file
@@ -8,10 +8,11 @@ use crate::{
         StringVec, COMMENTS_BY_POST, GROUPS, POSTS, POSTS_BY_GROUP, POSTS_BY_USER, UNSEEN_POSTS,
     },
     types::{
+        comments::Comments,
         notification::{NewNotification, NotificationType},
         posts::{NewPost, Post, UpdatePost},
     },
-    utils::{add_notification, generate_unique_id},
+    utils::{add_notifications, generate_unique_id},
 };
 
 impl Post {
@@ -131,6 +132,7 @@ impl Post {
                     format!("{} : {}...", content_preview, post_content.clone())
                 };
 
+                let mut deliveries = Vec::new();
                 for member in group_members {
                     // Don't send notification to the post author
                     if member.user_id != post_author {
@@ -138,9 +140,7 @@ impl Post {
                             notification_type: NotificationType::Message,
                             content: content_preview.clone(),
                         };
-
-                        // Send notification (ignore errors)
-                        let _ = add_notification(member.user_id, notification).await;
+                        deliveries.push((member.user_id, notification));
 
                         // add the new post as unseen
                         UNSEEN_POSTS.with(|unseen_posts| {
@@ -163,6 +163,9 @@ impl Post {
                         });
                     }
                 }
+
+                // Send notifications in one batch (ignore errors)
+                let _ = add_notifications(deliveries).await;
                 drop(content_preview);
                 drop(group_id_clone);
                 drop(post_content);
@@ -224,6 +227,8 @@ impl Post {
             }
         });
 
+        // * remove the post comments before dropping its comments index
+        Comments::delete_post_comments(post_id);
         COMMENTS_BY_POST.with(|comments_by_post| {
             comments_by_post.borrow_mut().remove(&post_id.to_string());
         });
@@ -0,0 +1,288 @@
+use crate::types::error::Error;
+use candid::Principal;
+use ic_cdk::api::{msg_caller, time};
+use ic_cdk::futures::spawn;
+
+use crate::{
+    storage::{StringVec, TAL3A, TAL3A_BY_GROUP, TAL3A_BY_USER, TAL3A_MEMBERS},
+    types::{
+        group::Group,
+        notification::{NewNotification, NotificationType},
+        tal3a::{NewTal3a, Tal3a},
+        tal3a_members::{JoinStatus, Tal3aMember, Tal3aMembers},
+    },
+    utils::{add_notifications, generate_unique_id},
+};
+
+// * TAL3A_MEMBERS is keyed by `{tal3a_id}:{user}` so a tal3a's members are a prefix range
+fn member_key(tal3a_id: &str, user_id: Principal) -> String {
+    format!("{}:{}", tal3a_id, user_id.to_text())
+}
+
+impl Tal3a {
+    pub async fn new(new_tal3a: NewTal3a) -> Result<Tal3a, Error> {
+        let user_id = msg_caller();
+
+        // * check if the user in the group first
+        let group = Group::get_by_id(&new_tal3a.group_id)?;
+        let group_members = group.get_group_members();
+        if !group_members.iter().any(|m| m.user_id == user_id) {
+            return Err(Error {
+                code: 403,
+                error: "User is not a member of the group".into(),
+                message: "User is not a member of the group".to_string(),
+            });
+        }
+
+        // * validate data
+        if new_tal3a.title.trim().is_empty() {
+            return Err(Error {
+                code: 400,
+                error: "Title is required".into(),
+                message: "Tal3a title cannot be empty".to_string(),
+            });
+        }
+        if new_tal3a.place.trim().is_empty() {
+            return Err(Error {
+                code: 400,
+                error: "Place is required".into(),
+                message: "Tal3a place cannot be empty".to_string(),
+            });
+        }
+        if new_tal3a.tal3a_date <= time() {
+            return Err(Error {
+                code: 400,
+                error: "Invalid tal3a date".into(),
+                message: "Tal3a date must be in the future".to_string(),
+            });
+        }
+        if new_tal3a.max_members == Some(0) {
+            return Err(Error {
+                code: 400,
+                error: "Invalid max members".into(),
+                message: "Max members must be greater than zero".to_string(),
+            });
+        }
+
+        let tal3a_id = generate_unique_id().await.map_err(|_| Error {
+            code: 500,
+            error: "Failed to generate unique tal3a ID".into(),
+            message: "Failed to generate unique tal3a ID".to_string(),
+        })?;
+
+        let tal3a = Tal3a {
+            group_id: new_tal3a.group_id,
+            tal3a_id,
+            created_by: user_id,
+            tal3a_date: new_tal3a.tal3a_date,
+            place: new_tal3a.place,
+            max_members: new_tal3a.max_members,
+            duration: new_tal3a.duration,
+            title: new_tal3a.title,
+            image: new_tal3a.image.unwrap_or_default(),
+            created_at: time(),
+        };
+
+        TAL3A.with(|tal3as| {
+            tal3as
+                .borrow_mut()
+                .insert(tal3a.tal3a_id.clone(), tal3a.clone());
+        });
+
+        // * add tal3a in TAL3A_BY_GROUP for fast indexing
+        TAL3A_BY_GROUP.with(|tal3a_by_group| {
+            let mut group_tal3as = tal3a_by_group
+                .borrow()
+                .get(&tal3a.group_id)
+                .unwrap_or(StringVec(Vec::new()));
+            group_tal3as.0.push(tal3a.tal3a_id.clone());
+            tal3a_by_group
+                .borrow_mut()
+                .insert(tal3a.group_id.clone(), group_tal3as);
+        });
+
+        // * the creator is going by default
+        tal3a.set_member_status(user_id, JoinStatus::Going);
+
+        // * notify group members about the new tal3a
+        let tal3a_title = tal3a.title.clone();
+        let group_name = group.name.clone();
+        ic_cdk_timers::set_timer(std::time::Duration::from_nanos(1), move || {
+            let group_members = group_members.clone();
+            let content = format!("New Tal3a in {} : {}", group_name, tal3a_title);
+            spawn(async move {
+                let deliveries = group_members
+                    .into_iter()
+                    // Don't send notification to the tal3a creator
+                    .filter(|member| member.user_id != user_id)
+                    .map(|member| {
+                        (
+                            member.user_id,
+                            NewNotification {
+                                content: content.clone(),
+                                notification_type: NotificationType::Alert,
+                            },
+                        )
+                    })
+                    .collect();
+                // Send notifications in one batch (ignore errors)
+                let _ = add_notifications(deliveries).await;
+            });
+        });
+
+        Ok(tal3a)
+    }
+
+    pub fn get_by_id(tal3a_id: &str) -> Result<Tal3a, Error> {
+        TAL3A.with(|tal3as| {
+            tal3as
+                .borrow()
+                .get(&tal3a_id.to_string())
+                .ok_or_else(|| Error {
+                    code: 404,
+                    error: "Tal3a not found".into(),
+                    message: "No tal3a exists with the given ID".to_string(),
+                })
+        })
+    }
+
+    // * a tal3a and its members are only visible to the members of its group
+    pub fn get_for_member(tal3a_id: &str, user_id: Principal) -> Result<Tal3a, Error> {
+        let tal3a = Self::get_by_id(tal3a_id)?;
+        tal3a.check_member(user_id)?;
+        Ok(tal3a)
+    }
+
+    fn check_member(&self, user_id: Principal) -> Result<(), Error> {
+        if !Group::get_members(&self.group_id)
+            .iter()
+            .any(|m| m.user_id == user_id)
+        {
+            return Err(Error {
+                code: 403,
+                error: "User is not a member of the group".into(),
+                message: "User is not a member of the group".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn rsvp(&self, user_id: Principal, status: JoinStatus) -> Result<Tal3aMember, Error> {
+        self.check_member(user_id)?;
+
+        if self.tal3a_date <= time() {
+            return Err(Error {
+                code: 400,
+                error: "Tal3a already started".into(),
+                message: "Cannot respond to a tal3a that already started".to_string(),
+            });
+        }
+
+        // * enforce max members on going
+        if status == JoinStatus::Going {
+            if let Some(max_members) = self.max_members {
+                let going = self
+                    .get_members()
+                    .iter()
+                    .filter(|m| m.status == JoinStatus::Going && m.user_id != user_id)
+                    .count();
+                if going >= max_members as usize {
+                    return Err(Error {
+                        code: 409,
+                        error: "Maximum members reached".into(),
+                        message: "Cannot join tal3a, maximum members reached".to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(self.set_member_status(user_id, status))
+    }
+
+    fn set_member_status(&self, user_id: Principal, status: JoinStatus) -> Tal3aMember {
+        let member = Tal3aMember {
+            group_id: self.group_id.clone(),
+            user_id,
+            tal3a_id: self.tal3a_id.clone(),
+            status,
+            joined_at: time(),
+        };
+
+        TAL3A_MEMBERS.with(|members| {
+            members.borrow_mut().insert(
+                member_key(&self.tal3a_id, user_id),
+                Tal3aMembers {
+                    member: member.clone(),
+                },
+            );
+        });
+
+        // * add tal3a ID to user's tal3a list
+        TAL3A_BY_USER.with(|tal3a_by_user| {
+            let mut user_tal3as = tal3a_by_user
+                .borrow()
+                .get(&user_id)
+                .unwrap_or(StringVec(Vec::new()));
+            if !user_tal3as.0.contains(&self.tal3a_id) {
+                user_tal3as.0.push(self.tal3a_id.clone());
+                tal3a_by_user.borrow_mut().insert(user_id, user_tal3as);
+            }
+        });
+
+        member
+    }
+
+    pub fn get_members(&self) -> Vec<Tal3aMember> {
+        let prefix = format!("{}:", self.tal3a_id);
+        TAL3A_MEMBERS.with(|members| {
+            members
+                .borrow()
+                .range(prefix.clone()..)
+                .take_while(|entry| entry.key().starts_with(&prefix))
+                .map(|entry| entry.value().member)
+                .collect()
+        })
+    }
+
+    // * upcoming tal3as of a group, soonest first (members only)
+    pub fn get_group_tal3as(group_id: &str) -> Vec<Tal3a> {
+        if !Group::get_members(group_id)
+            .iter()
+            .any(|m| m.user_id == msg_caller())
+        {
+            return Vec::new();
+        }
+
+        let tal3a_ids = TAL3A_BY_GROUP.with(|tal3a_by_group| {
+            tal3a_by_group
+                .borrow()
+                .get(&group_id.to_string())
+                .map(|ids| ids.0)
+                .unwrap_or_default()
+        });
+        Self::upcoming(tal3a_ids)
+    }
+
+    // * upcoming tal3as the user responded to, soonest first
+    pub fn get_user_tal3as(user_id: Principal) -> Vec<Tal3a> {
+        let tal3a_ids = TAL3A_BY_USER.with(|tal3a_by_user| {
+            tal3a_by_user
+                .borrow()
+                .get(&user_id)
+                .map(|ids| ids.0)
+                .unwrap_or_default()
+        });
+        Self::upcoming(tal3a_ids)
+    }
+
+    fn upcoming(tal3a_ids: Vec<String>) -> Vec<Tal3a> {
+        let now = time();
+        let mut tal3as: Vec<Tal3a> = tal3a_ids
+            .iter()
+            .filter_map(|id| Self::get_by_id(id).ok())
+            .filter(|tal3a| tal3a.tal3a_date > now)
+            .collect();
+        tal3as.sort_by_key(|tal3a| tal3a.tal3a_date);
+        tal3as
+    }
+}
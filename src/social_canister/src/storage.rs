@@ -2,130 +2,181 @@ use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemor
 use ic_stable_structures::storable::Bound;
 use ic_stable_structures::Storable;
 
-use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
 use std::borrow::Cow;
 use std::cell::RefCell;
 
 use candid::Principal;
+use versioned::Versioned;
 
 use crate::types::likes::Likes;
 use crate::types::{comments, group, group_members, likes, posts, tal3a, tal3a_members};
+use crate::types::quarantine::QuarantinedRecord;
 
 use candid::{Decode, Encode};
 
 type _Memory = VirtualMemory<DefaultMemoryImpl>;
 
 // * Implement the `Storable` trait for the structs.
-// * convert structs to bytes to be `Storable` stored in stable memory
+// * convert structs to bytes to be `Storable` stored in stable memory,
+// * domain structs go through the versioned envelope (see the `versioned` crate)
+impl Versioned for comments::Comments {
+    const VERSION: u8 = 1;
+}
+
 impl Storable for comments::Comments {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
     }
 }
 
+impl Versioned for group::Group {
+    const VERSION: u8 = 1;
+}
+
 impl Storable for group::Group {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
     }
 }
 
+impl Versioned for group_members::GroupMembers {
+    const VERSION: u8 = 1;
+}
+
 impl Storable for group_members::GroupMembers {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
     }
 }
 
+impl Versioned for likes::Likes {
+    const VERSION: u8 = 1;
+}
+
 impl Storable for likes::Likes {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
     }
 }
 
+impl Versioned for tal3a::Tal3a {
+    const VERSION: u8 = 1;
+}
+
 impl Storable for tal3a::Tal3a {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
     }
 }
 
+impl Versioned for posts::Post {
+    const VERSION: u8 = 1;
+}
+
 impl Storable for posts::Post {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
     }
 }
 
+impl Versioned for tal3a_members::Tal3aMembers {
+    const VERSION: u8 = 1;
+}
+
 impl Storable for tal3a_members::Tal3aMembers {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
-        Cow::Owned(Encode!(self).unwrap())
+        Cow::Owned(versioned::encode(self))
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        versioned::decode_or_trap(&bytes)
     }
 
     const BOUND: Bound = Bound::Unbounded;
 
     fn into_bytes(self) -> Vec<u8> {
-        Encode!(&self).unwrap()
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for QuarantinedRecord {
+    const VERSION: u8 = 1;
+}
+
+impl Storable for QuarantinedRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
     }
 }
 
@@ -267,4 +318,201 @@ thread_local! {
             )
         );
 
+    // * version of the stored data shapes, see `upgrade.rs`
+    pub static SCHEMA_VERSION: RefCell<StableCell<u32, _Memory>> =
+        RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get( MemoryId::new(17))),
+                0,
+            )
+        );
+
+    // * records that failed to decode during an upgrade
+    pub static QUARANTINE: RefCell<StableBTreeMap<u64, QuarantinedRecord, _Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get( MemoryId::new(18))),
+            )
+        );
+}
+
+// * move records that no longer decode into QUARANTINE, returns how many were moved.
+// * must run before any of the primary stores is first used
+pub fn quarantine_undecodable_records() -> usize {
+    use crate::types::{
+        comments::Comments, group::Group, group_members::GroupMembers, posts::Post, tal3a::Tal3a,
+        tal3a_members::Tal3aMembers,
+    };
+    use versioned::quarantine_undecodable;
+
+    let memory = |id: u8| MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(id)));
+
+    let records = [
+        quarantine_undecodable::<String, Group, _>("groups", memory(0)),
+        quarantine_undecodable::<String, Post, _>("posts", memory(1)),
+        quarantine_undecodable::<String, Comments, _>("comments", memory(2)),
+        quarantine_undecodable::<String, Tal3a, _>("tal3a", memory(4)),
+        quarantine_undecodable::<String, GroupMembers, _>("group_members", memory(5)),
+        quarantine_undecodable::<String, Tal3aMembers, _>("tal3a_members", memory(6)),
+        quarantine_undecodable::<String, Likes, _>("likes_by_post", memory(11)),
+        quarantine_undecodable::<Principal, Likes, _>("likes_by_user", memory(12)),
+    ]
+    .concat();
+
+    let count = records.len();
+    let quarantined_at = ic_cdk::api::time();
+    QUARANTINE.with(|quarantine| {
+        let mut quarantine = quarantine.borrow_mut();
+        for record in records {
+            let id = quarantine.last_key_value().map_or(0, |(id, _)| id + 1);
+            quarantine.insert(
+                id,
+                QuarantinedRecord {
+                    store: record.store,
+                    key: record.key,
+                    bytes: record.bytes,
+                    error: record.error,
+                    quarantined_at,
+                },
+            );
+        }
+    });
+    count
+}
+
+pub fn get_quarantined_records() -> Vec<QuarantinedRecord> {
+    QUARANTINE.with(|quarantine| quarantine.borrow().values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::group::Group;
+    use crate::types::group_members::{GroupMember, GroupMembers};
+    use crate::types::posts::Post;
+    use crate::types::sport::Sports;
+    use crate::types::tal3a::Tal3a;
+
+    fn principal() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn group() -> Group {
+        Group {
+            id: "group".to_string(),
+            governorate_id: 1,
+            city_id: 2,
+            name: "Cairo runners".to_string(),
+            sport_type: Sports::Football,
+            description: "Weekly matches".to_string(),
+            created_at: 1,
+            created_by: principal(),
+            image: vec![1, 2, 3],
+            parent_group_id: None,
+            public: true,
+            members: 4,
+            posts: 2,
+        }
+    }
+
+    fn tal3a() -> Tal3a {
+        Tal3a {
+            group_id: "group".to_string(),
+            tal3a_id: "tal3a".to_string(),
+            created_by: principal(),
+            tal3a_date: 10,
+            place: "Garden City".to_string(),
+            max_members: Some(10),
+            duration: None,
+            title: "Friday match".to_string(),
+            image: Vec::new(),
+            created_at: 1,
+        }
+    }
+
+    fn post() -> Post {
+        Post {
+            group_id: "group".to_string(),
+            post_id: "post".to_string(),
+            author: principal(),
+            content: "See you on Friday".to_string(),
+            images: None,
+            created_at: 1,
+            updated_at: 2,
+            likes: 3,
+            comments: 1,
+        }
+    }
+
+    // * records written before the versioned envelope are bare Candid, the
+    // * stored shapes did not change since, so the current types encode them
+
+    #[test]
+    fn reads_baseline_group() {
+        let decoded = Group::from_bytes(Cow::Owned(Encode!(&group()).unwrap()));
+        assert_eq!(decoded.name, "Cairo runners");
+        assert_eq!(decoded.sport_type, Sports::Football);
+        assert_eq!(decoded.members, 4);
+    }
+
+    #[test]
+    fn round_trips_group() {
+        let bytes = group().to_bytes().into_owned();
+        assert_eq!(bytes[0], Group::VERSION);
+
+        let decoded = Group::from_bytes(Cow::Owned(bytes));
+        assert_eq!(decoded.id, "group");
+        assert_eq!(decoded.image, vec![1, 2, 3]);
+        assert!(decoded.public);
+    }
+
+    #[test]
+    fn reads_baseline_tal3a() {
+        let decoded = Tal3a::from_bytes(Cow::Owned(Encode!(&tal3a()).unwrap()));
+        assert_eq!(decoded.tal3a_id, "tal3a");
+        assert_eq!(decoded.max_members, Some(10));
+    }
+
+    #[test]
+    fn round_trips_tal3a() {
+        let bytes = tal3a().to_bytes().into_owned();
+        assert_eq!(bytes[0], Tal3a::VERSION);
+
+        let decoded = Tal3a::from_bytes(Cow::Owned(bytes));
+        assert_eq!(decoded.title, "Friday match");
+        assert_eq!(decoded.created_by, principal());
+    }
+
+    #[test]
+    fn reads_baseline_post() {
+        let decoded = Post::from_bytes(Cow::Owned(Encode!(&post()).unwrap()));
+        assert_eq!(decoded.content, "See you on Friday");
+        assert_eq!(decoded.likes, 3);
+    }
+
+    #[test]
+    fn round_trips_post() {
+        let bytes = post().to_bytes().into_owned();
+        assert_eq!(bytes[0], Post::VERSION);
+
+        let decoded = Post::from_bytes(Cow::Owned(bytes));
+        assert_eq!(decoded.post_id, "post");
+        assert_eq!(decoded.updated_at, 2);
+    }
+
+    #[test]
+    fn round_trips_group_members() {
+        let members = GroupMembers {
+            members: vec![GroupMember {
+                group_id: "group".to_string(),
+                user_id: principal(),
+                joined_at: 1,
+            }],
+        };
+        let bare = GroupMembers::from_bytes(Cow::Owned(Encode!(&members).unwrap()));
+        assert_eq!(bare.members.len(), 1);
+
+        let decoded = GroupMembers::from_bytes(members.to_bytes());
+        assert_eq!(decoded.members[0].user_id, principal());
+    }
 }
@@ -17,3 +17,16 @@ pub struct Comments {
     pub created_at: u64,
     pub updated_at: u64,
 }
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct NewComment {
+    pub post_id: String,
+    pub content: String,
+    pub image: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct UpdateComment {
+    pub content: Option<String>,
+    pub image: Option<Vec<u8>>,
+}
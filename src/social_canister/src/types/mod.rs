@@ -7,6 +7,7 @@ pub mod group_members;
 pub mod likes;
 pub mod notification;
 pub mod posts;
+pub mod quarantine;
 pub mod sport;
 pub mod tal3a;
 pub mod tal3a_members;
@@ -17,4 +17,15 @@ pub struct Tal3a {
     pub title: String,
     pub image: Vec<u8>,
     pub created_at: u64,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct NewTal3a {
+    pub group_id: String,
+    pub tal3a_date: u64, // nanoseconds
+    pub place: String,
+    pub max_members: Option<u32>,
+    pub duration: Option<u32>,
+    pub title: String,
+    pub image: Option<Vec<u8>>,
+}
@@ -0,0 +1,82 @@
+use crate::storage::{
+    quarantine_undecodable_records, COMMENTS, COMMENTS_BY_POST, COMMENTS_BY_USER, GROUPS,
+    GROUPS_BY_USER, GROUP_MEMBERS, LIKES_BY_POST, LIKES_BY_USER, POSTS, POSTS_BY_GROUP,
+    POSTS_BY_USER, QUARANTINE, SCHEMA_VERSION, TAL3A, TAL3A_BY_GROUP, TAL3A_BY_USER, TAL3A_MEMBERS,
+    UNSEEN_POSTS,
+};
+
+// * bump this and add a step to `migrate` whenever a stored shape (e.g. `Group`) changes
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+pub fn init() {
+    set_schema_version(CURRENT_SCHEMA_VERSION);
+}
+
+pub fn pre_upgrade() {
+    // * all state is in stable structures, only make sure it is readable
+    validate_memory_layout();
+}
+
+pub fn post_upgrade() {
+    // * has to run before the typed maps are opened below
+    let quarantined = quarantine_undecodable_records();
+    if quarantined > 0 {
+        ic_cdk::println!("Quarantined {} undecodable records", quarantined);
+    }
+
+    validate_memory_layout();
+
+    let stored_version = SCHEMA_VERSION.with(|version| *version.borrow().get());
+    if stored_version > CURRENT_SCHEMA_VERSION {
+        ic_cdk::api::trap(format!(
+            "Cannot downgrade schema from version {} to {}",
+            stored_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    for version in stored_version..CURRENT_SCHEMA_VERSION {
+        migrate(version);
+    }
+    set_schema_version(CURRENT_SCHEMA_VERSION);
+}
+
+// * migrate stored data from `version` to `version + 1`
+fn migrate(version: u32) {
+    match version {
+        // * deployments before schema versioning, the stored shapes are unchanged
+        0 => {}
+        // * records are now written in the versioned envelope (see the `versioned` crate),
+        // * bare Candid records are still read as version 0
+        1 => {}
+        _ => ic_cdk::api::trap(format!("No migration from schema version {}", version)),
+    }
+}
+
+// * loading every stable structure checks its header, a mismatched
+// * memory layout traps here and the upgrade is rolled back
+fn validate_memory_layout() {
+    GROUPS.with(|m| m.borrow().len());
+    POSTS.with(|m| m.borrow().len());
+    COMMENTS.with(|m| m.borrow().len());
+    TAL3A.with(|m| m.borrow().len());
+    GROUP_MEMBERS.with(|m| m.borrow().len());
+    TAL3A_MEMBERS.with(|m| m.borrow().len());
+    POSTS_BY_GROUP.with(|m| m.borrow().len());
+    POSTS_BY_USER.with(|m| m.borrow().len());
+    COMMENTS_BY_POST.with(|m| m.borrow().len());
+    COMMENTS_BY_USER.with(|m| m.borrow().len());
+    LIKES_BY_POST.with(|m| m.borrow().len());
+    LIKES_BY_USER.with(|m| m.borrow().len());
+    GROUPS_BY_USER.with(|m| m.borrow().len());
+    TAL3A_BY_GROUP.with(|m| m.borrow().len());
+    TAL3A_BY_USER.with(|m| m.borrow().len());
+    UNSEEN_POSTS.with(|m| m.borrow().len());
+    QUARANTINE.with(|m| m.borrow().len());
+    SCHEMA_VERSION.with(|version| *version.borrow().get());
+}
+
+fn set_schema_version(version: u32) {
+    SCHEMA_VERSION.with(|cell| {
+        cell.borrow_mut().set(version);
+    });
+}
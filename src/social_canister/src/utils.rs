@@ -1,6 +1,6 @@
 use crate::types::city::CityData;
 use crate::types::error::Error;
-use crate::types::notification::NewNotification;
+use crate::types::notification::{NewNotification, NotificationDeliveryError};
 use base64::Engine;
 use candid::Principal;
 use ic_cdk::api::msg_caller;
@@ -44,16 +44,42 @@ pub async fn add_notification(
     notification: NewNotification,
 ) -> Result<(), Error> {
     let canister_id = get_user_canister_id()?;
-    ic_cdk::call::Call::unbounded_wait(canister_id, "add_notification")
+    ic_cdk::call::Call::unbounded_wait(canister_id, "deliver_notification")
         .with_args(&(user_id, notification))
         .await
         .map_err(|e| Error {
             code: 500,
             error: format!("User canister call error: {}", e),
             message: "Failed to add notification".into(),
-        })?;
+        })?
+        .candid::<Result<(), NotificationDeliveryError>>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to add notification".into(),
+        })?
+        .map_err(Error::from)
+}
 
-    Ok(())
+pub async fn add_notifications(
+    deliveries: Vec<(Principal, NewNotification)>,
+) -> Result<Vec<Result<(), NotificationDeliveryError>>, Error> {
+    let canister_id = get_user_canister_id()?;
+    ic_cdk::call::Call::unbounded_wait(canister_id, "deliver_notifications")
+        .with_arg(&deliveries)
+        .await
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("User canister call error: {}", e),
+            message: "Failed to add notifications".into(),
+        })?
+        .candid::<Result<Vec<Result<(), NotificationDeliveryError>>, NotificationDeliveryError>>()
+        .map_err(|e| Error {
+            code: 500,
+            error: format!("Candid decoding failed: {}", e),
+            message: "Failed to add notifications".into(),
+        })?
+        .map_err(Error::from)
 }
 
 pub async fn get_current_user() -> Result<(), Error> {
@@ -30,3 +30,16 @@ fn is_update_method(method_name: &str) -> bool {
         .iter()
         .any(|&pattern| method_name.starts_with(pattern) || method_name.contains(pattern))
 }
+
+// * sibling canisters allowed to call service-to-service endpoints
+pub fn is_sibling_canister(caller: &Principal) -> bool {
+    [
+        option_env!("CANISTER_ID_EVENT_CANISTER"),
+        option_env!("CANISTER_ID_SOCIAL_CANISTER"),
+        option_env!("CANISTER_ID_OWNERS_CANISTER"),
+    ]
+    .iter()
+    .flatten()
+    .filter_map(|id| Principal::from_text(id).ok())
+    .any(|id| id == *caller)
+}
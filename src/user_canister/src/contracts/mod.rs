@@ -1,4 +1,6 @@
 pub mod users;
 pub mod cities;
 pub mod governorate;
-pub mod middleware;
\ No newline at end of file
+pub mod middleware;
+pub mod notifications;
+pub mod quarantine;
@@ -0,0 +1,86 @@
+use crate::contracts::middleware::is_sibling_canister;
+use crate::types::error::Error;
+use crate::types::notification::{
+    NewNotification, Notification, NotificationCursor, NotificationDeliveryError, NotificationPage,
+};
+use crate::types::user::User;
+use candid::Principal;
+use ic_cdk;
+use ic_cdk::{query, update};
+
+#[update]
+async fn add_notification(notification: NewNotification) -> Result<(), Error> {
+    let caller = ic_cdk::api::msg_caller();
+    User::get_user(caller)?;
+    Notification::add(caller, notification).await
+}
+
+// * service-to-service: deliver a notification to another user's inbox
+#[update]
+async fn deliver_notification(
+    recipient: Principal,
+    notification: NewNotification,
+) -> Result<(), NotificationDeliveryError> {
+    let caller = ic_cdk::api::msg_caller();
+    if !is_sibling_canister(&caller) {
+        return Err(NotificationDeliveryError::UnauthorizedCaller(caller));
+    }
+    Notification::deliver(vec![(recipient, notification)])
+        .await?
+        .remove(0)
+}
+
+// * service-to-service: deliver many notifications, one result per delivery
+#[update]
+async fn deliver_notifications(
+    deliveries: Vec<(Principal, NewNotification)>,
+) -> Result<Vec<Result<(), NotificationDeliveryError>>, NotificationDeliveryError> {
+    let caller = ic_cdk::api::msg_caller();
+    if !is_sibling_canister(&caller) {
+        return Err(NotificationDeliveryError::UnauthorizedCaller(caller));
+    }
+    Notification::deliver(deliveries).await
+}
+
+#[query]
+fn get_notifications(cursor: Option<NotificationCursor>, limit: Option<u32>) -> NotificationPage {
+    Notification::get_page(ic_cdk::api::msg_caller(), cursor, limit)
+}
+
+#[query]
+fn get_unread_count() -> u64 {
+    Notification::unread_count(ic_cdk::api::msg_caller())
+}
+
+#[update]
+fn mark_notification_as_read(notification_id: String) -> Result<(), Error> {
+    Notification::mark_as_read(ic_cdk::api::msg_caller(), &notification_id)
+}
+
+#[update]
+fn mark_all_as_read() -> u64 {
+    Notification::mark_all_as_read(ic_cdk::api::msg_caller())
+}
+
+#[update]
+fn delete_notification(notification_id: String) -> Result<(), Error> {
+    Notification::delete(ic_cdk::api::msg_caller(), &notification_id)
+}
+
+#[query]
+fn get_notification_retention() -> u64 {
+    Notification::get_retention()
+}
+
+// * controllers only: how long read notifications are kept (nano seconds)
+#[update]
+fn set_notification_retention(retention: u64) -> Result<(), Error> {
+    if !ic_cdk::api::is_controller(&ic_cdk::api::msg_caller()) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only controllers can change the notification retention".to_string(),
+        });
+    }
+    Notification::set_retention(retention)
+}
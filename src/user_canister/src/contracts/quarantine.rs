@@ -0,0 +1,17 @@
+use crate::storage::get_quarantined_records as quarantined_records;
+use crate::types::error::Error;
+use crate::types::quarantine::QuarantinedRecord;
+use ic_cdk::query;
+
+// * controllers only: records that failed to decode during an upgrade
+#[query]
+fn get_quarantined_records() -> Result<Vec<QuarantinedRecord>, Error> {
+    if !ic_cdk::api::is_controller(&ic_cdk::api::msg_caller()) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only controllers can read quarantined records".to_string(),
+        });
+    }
+    Ok(quarantined_records())
+}
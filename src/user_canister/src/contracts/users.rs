@@ -1,6 +1,8 @@
+use crate::contracts::middleware::is_sibling_canister;
 use crate::types::activity::UserActivity;
 use crate::types::error::Error;
-use crate::types::notification::{NewNotification, NotificationType};
+use crate::types::notification::{NewNotification, Notification, NotificationType};
+use crate::types::skill_level::SportLevel;
 use crate::types::user::{PublicUser, RegisteringUser, UpdatingUser, User};
 use candid::Principal;
 use ic_cdk;
@@ -18,6 +20,37 @@ fn get_user(principal_id: Principal) -> Result<PublicUser, Error> {
     Ok(user.to_public())
 }
 
+// * service-to-service: the free days of a user, used by the event filters
+#[query]
+fn get_user_free_days(principal_id: Principal) -> Result<Option<Vec<u8>>, Error> {
+    let caller = ic_cdk::api::msg_caller();
+    if !is_sibling_canister(&caller) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only sibling canisters can read free days".to_string(),
+        });
+    }
+    Ok(User::get_user(principal_id)?.free_days)
+}
+
+// * service-to-service: the self-declared skill levels, used to start the
+// * skill ratings of the event canister
+#[query]
+fn get_user_skill_levels(principal_id: Principal) -> Result<Vec<SportLevel>, Error> {
+    let caller = ic_cdk::api::msg_caller();
+    if !is_sibling_canister(&caller) {
+        return Err(Error {
+            code: 403,
+            error: "Forbidden".to_string(),
+            message: "Only sibling canisters can read skill levels".to_string(),
+        });
+    }
+    Ok(User::get_user(principal_id)?
+        .skill_levels
+        .unwrap_or_default())
+}
+
 #[query]
 fn get_current_user() -> Result<User, Error> {
     let mut user = User::get_user(ic_cdk::api::msg_caller()).map_err(|e| e)?;
@@ -43,18 +76,21 @@ pub fn ping() -> Result<(), Error> {
 #[update]
 async fn create_user(new_user: RegisteringUser) -> Result<User, Error> {
     match User::new(new_user) {
-        Ok(mut user) => {
+        Ok(user) => {
             // * Welcome message notification
-            user.add_notification(NewNotification {
-                content: format!(
-                    "Hey {} 👋,\n
+            Notification::add(
+                user.principal_id,
+                NewNotification {
+                    content: format!(
+                        "Hey {} 👋,\n
             Welcome to Tal3a 🎉, your sports journey starts here!\n
             Here you’ll find people like you who love sports in your area.\n
             Get started now: Join a group, participate in an event, or create one yourself 💪⚽🚴‍♂",
-                    user.username
-                ),
-                notification_type: NotificationType::Message,
-            })
+                        user.username
+                    ),
+                    notification_type: NotificationType::Message,
+                },
+            )
             .await
             .map_err(|e| e)?;
             Ok(user)
@@ -63,40 +99,6 @@ async fn create_user(new_user: RegisteringUser) -> Result<User, Error> {
     }
 }
 
-#[update]
-async fn add_notification(notification: NewNotification) -> Result<(), Error> {
-    let caller = ic_cdk::api::msg_caller();
-    let user = User::get_user(caller).ok();
-    if let Some(mut user) = user {
-        user.add_notification(notification)
-            .await
-            .map_err(|e| e)
-            .map_err(|e| e)
-    } else {
-        Err(Error {
-            code: 404,
-            error: "User not found".to_string(),
-            message: "No user exists with the given principal ID".to_string(),
-        })
-    }
-}
-
-#[update]
-fn mark_notification_as_read(notification_id: String) -> Result<(), Error> {
-    let caller = ic_cdk::api::msg_caller();
-    let user = User::get_user(caller).ok();
-    if let Some(mut user) = user {
-        user.mark_notification_as_read(notification_id)
-            .map_err(|e| e)
-    } else {
-        Err(Error {
-            code: 404,
-            error: "User not found".to_string(),
-            message: "No user exists with the given principal ID".to_string(),
-        })
-    }
-}
-
 #[update]
 fn update_profile(new_data: UpdatingUser) -> Result<(), Error> {
     let caller = ic_cdk::api::msg_caller();
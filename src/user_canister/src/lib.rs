@@ -2,18 +2,39 @@ mod contracts;
 mod services;
 mod storage;
 mod types;
+mod upgrade;
 
 use crate::types::{
     activity::UserActivity,
     city::CityData,
     error::Error,
     governorate::GovernorateData,
-    notification::NewNotification,
+    notification::{
+        NewNotification, NotificationCursor, NotificationDeliveryError, NotificationPage,
+    },
+    quarantine::QuarantinedRecord,
+    skill_level::SportLevel,
     user::{PublicUser, RegisteringUser, UpdatingUser, User},
 };
 
 use candid::Principal;
 use ic_cdk;
+use ic_cdk::{init, post_upgrade, pre_upgrade};
+
+#[init]
+fn init() {
+    upgrade::init();
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    upgrade::pre_upgrade();
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    upgrade::post_upgrade();
+}
 
 // * export contracts
 ic_cdk::export_candid!();
@@ -1,3 +1,4 @@
 pub mod city;
 pub mod governorate;
-pub mod user;
\ No newline at end of file
+pub mod notification;
+pub mod user;
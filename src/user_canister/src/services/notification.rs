@@ -0,0 +1,261 @@
+use crate::storage::{
+    take_legacy_notifications, NotificationKey, NOTIFICATIONS, NOTIFICATION_RETENTION, USERS,
+};
+use crate::types::{
+    error::Error,
+    notification::{
+        NewNotification, Notification, NotificationCursor, NotificationDeliveryError,
+        NotificationPage,
+    },
+    user::User,
+};
+use base64::Engine;
+use candid::Principal;
+use ic_cdk::api::time;
+use ic_cdk::management_canister::raw_rand;
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+
+// * every inbox key of a user sorts between these two keys
+fn inbox_start(user_id: Principal) -> NotificationKey {
+    (user_id, 0, String::new())
+}
+
+fn inbox_end(user_id: Principal) -> NotificationKey {
+    (user_id, u64::MAX, String::new())
+}
+
+impl Notification {
+    pub async fn add(user_id: Principal, notification: NewNotification) -> Result<(), Error> {
+        let id = Self::generate_id().await.map_err(|e| Error {
+            code: 500,
+            error: e,
+            message: "Internal Server Error".to_string(),
+        })?;
+        Self::push(user_id, notification, id);
+        Ok(())
+    }
+
+    // * deliver notifications sent by sibling canisters, one result per delivery
+    pub async fn deliver(
+        deliveries: Vec<(Principal, NewNotification)>,
+    ) -> Result<Vec<Result<(), NotificationDeliveryError>>, NotificationDeliveryError> {
+        // * one random base ID for the whole batch
+        let base_id = Self::generate_id()
+            .await
+            .map_err(NotificationDeliveryError::Internal)?;
+
+        Ok(deliveries
+            .into_iter()
+            .enumerate()
+            .map(|(index, (recipient, notification))| {
+                if notification.content.trim().is_empty() {
+                    return Err(NotificationDeliveryError::InvalidNotification(
+                        "Notification content cannot be empty".to_string(),
+                    ));
+                }
+                if !USERS.with(|users| users.borrow().contains_key(&recipient)) {
+                    return Err(NotificationDeliveryError::RecipientNotFound(recipient));
+                }
+                Self::push(recipient, notification, format!("{}-{}", base_id, index));
+                Ok(())
+            })
+            .collect())
+    }
+
+    fn push(user_id: Principal, notification: NewNotification, id: String) {
+        let created_at = time();
+        NOTIFICATIONS.with(|notifications| {
+            notifications.borrow_mut().insert(
+                (user_id, created_at, id.clone()),
+                Notification {
+                    content: notification.content,
+                    created_at,
+                    is_read: false,
+                    notification_type: notification.notification_type,
+                    id,
+                },
+            );
+        });
+        Self::prune(user_id);
+    }
+
+    async fn generate_id() -> Result<String, String> {
+        raw_rand().await.map_err(|e| e.to_string()).map(|bytes| {
+            // Use base64 encoding for shorter, URL-safe IDs
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes)
+        })
+    }
+
+    // * newest first, continuing after the cursor when one is given
+    pub fn get_page(
+        user_id: Principal,
+        cursor: Option<NotificationCursor>,
+        limit: Option<u32>,
+    ) -> NotificationPage {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE) as usize;
+        let end = match cursor {
+            Some(cursor) => (user_id, cursor.created_at, cursor.id),
+            None => inbox_end(user_id),
+        };
+
+        let notifications: Vec<Notification> = NOTIFICATIONS.with(|notifications| {
+            notifications
+                .borrow()
+                .range(inbox_start(user_id)..end)
+                .rev()
+                .take(limit)
+                .map(|entry| entry.value())
+                .collect()
+        });
+
+        let next_cursor = if notifications.len() == limit {
+            notifications.last().map(|last| NotificationCursor {
+                created_at: last.created_at,
+                id: last.id.clone(),
+            })
+        } else {
+            None
+        };
+
+        NotificationPage {
+            notifications,
+            next_cursor,
+        }
+    }
+
+    pub fn unread_count(user_id: Principal) -> u64 {
+        NOTIFICATIONS.with(|notifications| {
+            notifications
+                .borrow()
+                .values_range(inbox_start(user_id)..inbox_end(user_id))
+                .filter(|notification| !notification.is_read)
+                .count() as u64
+        })
+    }
+
+    fn find_key(user_id: Principal, notification_id: &str) -> Result<NotificationKey, Error> {
+        NOTIFICATIONS.with(|notifications| {
+            notifications
+                .borrow()
+                .keys_range(inbox_start(user_id)..inbox_end(user_id))
+                .find(|key| key.2 == notification_id)
+                .ok_or(Error {
+                    code: 404,
+                    error: "Notification not found".to_string(),
+                    message: "No notification exists with the given ID".to_string(),
+                })
+        })
+    }
+
+    pub fn mark_as_read(user_id: Principal, notification_id: &str) -> Result<(), Error> {
+        let key = Self::find_key(user_id, notification_id)?;
+        NOTIFICATIONS.with(|notifications| {
+            let mut notifications = notifications.borrow_mut();
+            if let Some(mut notification) = notifications.get(&key) {
+                notification.is_read = true;
+                notifications.insert(key, notification);
+            }
+        });
+        Ok(())
+    }
+
+    // * returns the number of notifications marked as read
+    pub fn mark_all_as_read(user_id: Principal) -> u64 {
+        let unread: Vec<(NotificationKey, Notification)> = NOTIFICATIONS.with(|notifications| {
+            notifications
+                .borrow()
+                .range(inbox_start(user_id)..inbox_end(user_id))
+                .map(|entry| (entry.key().clone(), entry.value()))
+                .filter(|(_, notification)| !notification.is_read)
+                .collect()
+        });
+
+        let count = unread.len() as u64;
+        NOTIFICATIONS.with(|notifications| {
+            let mut notifications = notifications.borrow_mut();
+            for (key, mut notification) in unread {
+                notification.is_read = true;
+                notifications.insert(key, notification);
+            }
+        });
+        Self::prune(user_id);
+        count
+    }
+
+    pub fn delete(user_id: Principal, notification_id: &str) -> Result<(), Error> {
+        let key = Self::find_key(user_id, notification_id)?;
+        NOTIFICATIONS.with(|notifications| {
+            notifications.borrow_mut().remove(&key);
+        });
+        Ok(())
+    }
+
+    pub fn delete_all(user_id: Principal) {
+        Self::remove_where(user_id, u64::MAX, |_| true);
+    }
+
+    // * drop read notifications older than the retention period
+    pub fn prune(user_id: Principal) {
+        let retention = NOTIFICATION_RETENTION.with(|retention| *retention.borrow().get());
+        let cutoff = time().saturating_sub(retention);
+        Self::remove_where(user_id, cutoff, |notification| notification.is_read);
+    }
+
+    fn remove_where(user_id: Principal, before: u64, predicate: impl Fn(&Notification) -> bool) {
+        NOTIFICATIONS.with(|notifications| {
+            let keys: Vec<NotificationKey> = notifications
+                .borrow()
+                .range(inbox_start(user_id)..(user_id, before, String::new()))
+                .filter(|entry| predicate(&entry.value()))
+                .map(|entry| entry.key().clone())
+                .collect();
+
+            let mut notifications = notifications.borrow_mut();
+            for key in keys {
+                notifications.remove(&key);
+            }
+        });
+    }
+
+    pub fn get_retention() -> u64 {
+        NOTIFICATION_RETENTION.with(|retention| *retention.borrow().get())
+    }
+
+    pub fn set_retention(retention: u64) -> Result<(), Error> {
+        if retention == 0 {
+            return Err(Error {
+                code: 400,
+                error: "Invalid retention".to_string(),
+                message: "Retention must be greater than zero".to_string(),
+            });
+        }
+        NOTIFICATION_RETENTION.with(|cell| {
+            cell.borrow_mut().set(retention);
+        });
+        Ok(())
+    }
+
+    // * move notifications kept inside `User` records into the inbox store
+    pub fn migrate_legacy() {
+        for (user_id, legacy) in take_legacy_notifications() {
+            NOTIFICATIONS.with(|notifications| {
+                let mut notifications = notifications.borrow_mut();
+                for notification in legacy {
+                    notifications.insert(
+                        (user_id, notification.created_at, notification.id.clone()),
+                        notification,
+                    );
+                }
+            });
+
+            // * write the user back without the inline notifications
+            if let Ok(user) = User::get_user(user_id) {
+                USERS.with(|users| {
+                    users.borrow_mut().insert(user_id, user);
+                });
+            }
+        }
+    }
+}
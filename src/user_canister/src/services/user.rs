@@ -1,4 +1,5 @@
 use crate::storage::USERS;
+use crate::types::skill_level::SportLevel;
 use crate::types::sport::Sports;
 use crate::types::user::PublicUser;
 use crate::types::{
@@ -6,13 +7,11 @@ use crate::types::{
     city::CityData,
     error::Error,
     governorate::GovernorateData,
-    notification::{NewNotification, Notification},
+    notification::Notification,
     user::{RegisteringUser, UpdatingUser, User, UserRole},
 };
-use base64::Engine;
 use candid::{Nat, Principal};
 use ic_cdk::api::time;
-use ic_cdk::management_canister::raw_rand;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 
@@ -113,6 +112,9 @@ impl User {
                 message: "You must select at least one sport".to_string(),
             });
         }
+        if let Some(skill_levels) = &registering_user.skill_levels {
+            validate_skill_levels(skill_levels, &registering_user.sports)?;
+        }
 
         let created_at = ic_cdk::api::time();
         let governorate =
@@ -132,10 +134,10 @@ impl User {
             role: UserRole::User,
             free_days: registering_user.free_days,
             is_online: true,           // default value
-            notifications: Vec::new(), // Initialize with an empty vector
             last_active: created_at,
             activity: Vec::new(),
             manual_status: false,
+            skill_levels: registering_user.skill_levels,
         };
 
         // Give welcome bonus asynchronously (ignore result here, log if needed)
@@ -215,6 +217,10 @@ impl User {
         if let Some(free_days) = updating_user.free_days {
             self.free_days = Some(free_days);
         }
+        if let Some(skill_levels) = updating_user.skill_levels {
+            validate_skill_levels(&skill_levels, &self.sports)?;
+            self.skill_levels = Some(skill_levels);
+        }
 
         USERS.with(|users| {
             users.borrow_mut().insert(self.principal_id, self.clone());
@@ -226,6 +232,7 @@ impl User {
         USERS.with(|users| {
             users.borrow_mut().remove(&self.principal_id);
         });
+        Notification::delete_all(self.principal_id);
         Ok(())
     }
 
@@ -238,50 +245,6 @@ impl User {
         Ok(())
     }
 
-    pub async fn add_notification(&mut self, notification: NewNotification) -> Result<(), Error> {
-        self.notifications.push(Notification {
-            content: notification.content,
-            created_at: time(),
-            is_read: false,
-            notification_type: notification.notification_type,
-            id: raw_rand()
-                .await
-                .map_err(|e| Error {
-                    code: 500,
-                    error: e.to_string(),
-                    message: "Internal Server Error".to_string(),
-                })
-                .map(|bytes| {
-                    // Use base64 encoding for shorter, URL-safe IDs
-                    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes)
-                })?,
-        });
-        USERS.with(|users| {
-            users.borrow_mut().insert(self.principal_id, self.clone());
-        });
-        Ok(())
-    }
-
-    pub fn mark_notification_as_read(&mut self, notification_id: String) -> Result<(), Error> {
-        if let Some(notification) = self
-            .notifications
-            .iter_mut()
-            .find(|n| n.id == notification_id)
-        {
-            notification.is_read = true;
-            USERS.with(|users| {
-                users.borrow_mut().insert(self.principal_id, self.clone());
-            });
-            Ok(())
-        } else {
-            Err(Error {
-                code: 404,
-                error: "Notification not found".to_string(),
-                message: "No notification exists with the given ID".to_string(),
-            })
-        }
-    }
-
     pub fn ping(&mut self) -> Result<(), Error> {
         self.last_active = time();
         USERS.with(|users| {
@@ -351,3 +314,27 @@ impl User {
         Ok(())
     }
 }
+
+// * one level per sport, only for the sports the user plays
+fn validate_skill_levels(skill_levels: &[SportLevel], sports: &[Sports]) -> Result<(), Error> {
+    for (index, skill_level) in skill_levels.iter().enumerate() {
+        if !sports.contains(&skill_level.sport) {
+            return Err(Error {
+                code: 400,
+                error: "Invalid skill level".to_string(),
+                message: "A skill level can only be set for your sports".to_string(),
+            });
+        }
+        if skill_levels[..index]
+            .iter()
+            .any(|other| other.sport == skill_level.sport)
+        {
+            return Err(Error {
+                code: 400,
+                error: "Invalid skill level".to_string(),
+                message: "Only one skill level can be set per sport".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
@@ -2,38 +2,118 @@ use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemor
 use ic_stable_structures::Storable;
 use ic_stable_structures::storable::Bound;
 
-use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
 use std::cell::RefCell;
 use std::borrow::Cow;
 
-use candid::Principal;
+use candid::{CandidType, Principal};
+use serde::Deserialize;
+use versioned::Versioned;
 
-use crate::types::user;
+use crate::types::{notification, quarantine::QuarantinedRecord, user};
+use notification::Notification;
 use user::User;
 
 use candid::{Encode, Decode};
 
 type _Memory = VirtualMemory<DefaultMemoryImpl>;
 
+// * notifications inbox key: (user, created_at, notification id)
+pub type NotificationKey = (Principal, u64, String);
+
+// * read notifications older than this are pruned (30 days in nano seconds)
+pub const DEFAULT_NOTIFICATION_RETENTION: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+
+impl Versioned for User {
+    const VERSION: u8 = 1;
+}
 
 // * Implement the `Storable` trait for the `User` struct.
 // * convert the `User` struct to bytes to be `Storable` stored in stable memory
 impl Storable for User {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+impl Versioned for Notification {
+    const VERSION: u8 = 1;
+}
+
+impl Storable for Notification {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
+// * `User` records written before the notifications inbox kept their
+// * notifications inline, this view only decodes that field
+// * (those records predate the versioned envelope, so they are bare Candid)
+#[derive(CandidType, Deserialize)]
+struct LegacyUserNotifications {
+    notifications: Option<Vec<Notification>>,
+}
+
+impl Storable for LegacyUserNotifications {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
         Cow::Owned(Encode!(self).unwrap())
     }
 
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        let (_, payload) = versioned::split(&bytes);
+        Decode!(payload, Self).unwrap_or(LegacyUserNotifications {
+            notifications: None,
+        })
     }
 
     const BOUND: Bound = Bound::Unbounded;
-    
+
     fn into_bytes(self) -> Vec<u8> {
         Encode!(&self).unwrap()
     }
 }
 
+impl Versioned for QuarantinedRecord {
+    const VERSION: u8 = 1;
+}
+
+impl Storable for QuarantinedRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(versioned::encode(self))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        versioned::decode_or_trap(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        versioned::encode(&self)
+    }
+}
+
 
 // * Thread-local storage for the memory manager
 thread_local! {
@@ -47,6 +127,236 @@ thread_local! {
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0))),
         )
     );
-    
-    pub static NEXT_ID: RefCell<u64> = RefCell::new(1);
-}
\ No newline at end of file
+
+    // * notifications inbox, ordered by user then creation time
+    pub static NOTIFICATIONS: RefCell<StableBTreeMap<NotificationKey, Notification, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(1))),
+        )
+    );
+
+    // * how long read notifications are kept (nano seconds)
+    pub static NOTIFICATION_RETENTION: RefCell<StableCell<u64, _Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2))),
+            DEFAULT_NOTIFICATION_RETENTION,
+        )
+    );
+
+    // * version of the stored data shapes, see `upgrade.rs`
+    pub static SCHEMA_VERSION: RefCell<StableCell<u32, _Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3))),
+            0,
+        )
+    );
+
+    pub static NEXT_ID: RefCell<StableCell<u64, _Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4))),
+            1,
+        )
+    );
+
+    // * records that failed to decode during an upgrade
+    pub static QUARANTINE: RefCell<StableBTreeMap<u64, QuarantinedRecord, _Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5))),
+        )
+    );
+}
+
+// * read the inline notifications of every stored user, must run before
+// * the users are written back without them
+pub fn take_legacy_notifications() -> Vec<(Principal, Vec<Notification>)> {
+    let legacy_users: StableBTreeMap<Principal, LegacyUserNotifications, _Memory> =
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0))));
+
+    legacy_users
+        .iter()
+        .filter_map(|entry| {
+            let notifications = entry.value().notifications?;
+            (!notifications.is_empty()).then(|| (*entry.key(), notifications))
+        })
+        .collect()
+}
+
+// * move records that no longer decode into QUARANTINE, returns how many were moved.
+// * must run before USERS and NOTIFICATIONS are first used
+pub fn quarantine_undecodable_records() -> usize {
+    let memory = |id: u8| MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(id)));
+
+    let records = [
+        versioned::quarantine_undecodable::<Principal, User, _>("users", memory(0)),
+        versioned::quarantine_undecodable::<NotificationKey, Notification, _>(
+            "notifications",
+            memory(1),
+        ),
+    ]
+    .concat();
+
+    let count = records.len();
+    let quarantined_at = ic_cdk::api::time();
+    QUARANTINE.with(|quarantine| {
+        let mut quarantine = quarantine.borrow_mut();
+        for record in records {
+            let id = quarantine.last_key_value().map_or(0, |(id, _)| id + 1);
+            quarantine.insert(
+                id,
+                QuarantinedRecord {
+                    store: record.store,
+                    key: record.key,
+                    bytes: record.bytes,
+                    error: record.error,
+                    quarantined_at,
+                },
+            );
+        }
+    });
+    count
+}
+
+pub fn get_quarantined_records() -> Vec<QuarantinedRecord> {
+    QUARANTINE.with(|quarantine| quarantine.borrow().values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::activity::UserActivity;
+    use crate::types::city::CityData;
+    use crate::types::governorate::GovernorateData;
+    use crate::types::notification::NotificationType;
+    use crate::types::skill_level::{SkillLevel, SportLevel};
+    use crate::types::sport::Sports;
+    use crate::types::user::UserRole;
+
+    // * `User` as first deployed: notifications inline, no skill levels
+    #[derive(CandidType)]
+    struct BaselineUser {
+        principal_id: Principal,
+        created_at: u64,
+        username: String,
+        governorate: GovernorateData,
+        city: CityData,
+        bio: Option<String>,
+        avatar_url: Option<Vec<u8>>,
+        sports: Vec<Sports>,
+        role: UserRole,
+        free_days: Option<Vec<u8>>,
+        is_online: bool,
+        notifications: Vec<Notification>,
+        last_active: u64,
+        activity: Vec<UserActivity>,
+        manual_status: bool,
+    }
+
+    fn principal() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn notification() -> Notification {
+        Notification {
+            content: "You have joined the event".to_string(),
+            created_at: 5,
+            is_read: false,
+            notification_type: NotificationType::Message,
+            id: "1".to_string(),
+        }
+    }
+
+    fn baseline_user() -> BaselineUser {
+        BaselineUser {
+            principal_id: principal(),
+            created_at: 1,
+            username: "tal3a".to_string(),
+            governorate: GovernorateData {
+                name: "Cairo".to_string(),
+                name_l1: "القاهرة".to_string(),
+                slug: "cairo".to_string(),
+                id: 1,
+            },
+            city: CityData {
+                name: "Garden City".to_string(),
+                name_l1: "جاردن سيتي".to_string(),
+                slug: "garden-city".to_string(),
+                id: 2,
+            },
+            bio: Some("bio".to_string()),
+            avatar_url: None,
+            sports: vec![Sports::Football],
+            role: UserRole::User,
+            free_days: Some(vec![5, 6]),
+            is_online: true,
+            notifications: vec![notification()],
+            last_active: 3,
+            activity: Vec::new(),
+            manual_status: false,
+        }
+    }
+
+    #[test]
+    fn reads_baseline_user() {
+        // * written before the versioned envelope, bare Candid
+        let bytes = Encode!(&baseline_user()).unwrap();
+        let user = User::from_bytes(Cow::Owned(bytes));
+
+        assert_eq!(user.principal_id, principal());
+        assert_eq!(user.username, "tal3a");
+        assert_eq!(user.city.id, 2);
+        assert_eq!(user.free_days, Some(vec![5, 6]));
+        assert!(user.sports == vec![Sports::Football]);
+        assert!(user.skill_levels.is_none());
+    }
+
+    #[test]
+    fn round_trips_user() {
+        let mut user = User::from_bytes(Cow::Owned(Encode!(&baseline_user()).unwrap()));
+        user.skill_levels = Some(vec![SportLevel {
+            sport: Sports::Football,
+            level: SkillLevel::Advanced,
+        }]);
+
+        let bytes = user.to_bytes().into_owned();
+        assert_eq!(bytes[0], User::VERSION);
+
+        let decoded = User::from_bytes(Cow::Owned(bytes));
+        assert_eq!(decoded.username, "tal3a");
+        assert_eq!(decoded.last_active, 3);
+        let levels = decoded.skill_levels.unwrap();
+        assert!(levels[0].sport == Sports::Football);
+        assert!(levels[0].level == SkillLevel::Advanced);
+    }
+
+    #[test]
+    fn reads_baseline_notification() {
+        let bytes = Encode!(&notification()).unwrap();
+        let decoded = Notification::from_bytes(Cow::Owned(bytes));
+        assert_eq!(decoded.content, notification().content);
+        assert_eq!(decoded.created_at, 5);
+        assert_eq!(decoded.id, "1");
+    }
+
+    #[test]
+    fn round_trips_notification() {
+        let bytes = notification().to_bytes().into_owned();
+        assert_eq!(bytes[0], Notification::VERSION);
+
+        let decoded = Notification::from_bytes(Cow::Owned(bytes));
+        assert_eq!(decoded.content, notification().content);
+        assert!(!decoded.is_read);
+        assert!(matches!(
+            decoded.notification_type,
+            NotificationType::Message
+        ));
+    }
+
+    #[test]
+    fn reads_legacy_inline_notifications() {
+        let bytes = Encode!(&baseline_user()).unwrap();
+        let legacy = LegacyUserNotifications::from_bytes(Cow::Owned(bytes));
+        let notifications = legacy.notifications.unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].id, "1");
+    }
+}
@@ -3,5 +3,7 @@ pub mod city;
 pub mod error;
 pub mod governorate;
 pub mod notification;
+pub mod quarantine;
+pub mod skill_level;
 pub mod sport;
 pub mod user;
@@ -1,4 +1,4 @@
-use candid::CandidType;
+use candid::{CandidType, Principal};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
@@ -23,3 +23,25 @@ pub struct NewNotification {
     pub content: String,
     pub notification_type: NotificationType,
 }
+
+// * position in the inbox, notifications older than the cursor come next
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct NotificationCursor {
+    pub created_at: u64,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    pub next_cursor: Option<NotificationCursor>,
+}
+
+// * errors returned to sibling canisters delivering notifications
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub enum NotificationDeliveryError {
+    UnauthorizedCaller(Principal),
+    RecipientNotFound(Principal),
+    InvalidNotification(String),
+    Internal(String),
+}
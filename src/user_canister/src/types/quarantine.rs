@@ -0,0 +1,13 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+// * stored record that could not be decoded during an upgrade, kept as is
+// * so it can be inspected and repaired instead of trapping on every read
+#[derive(Debug, Clone, Deserialize, Serialize, CandidType)]
+pub struct QuarantinedRecord {
+    pub store: String,
+    pub key: Vec<u8>,
+    pub bytes: Vec<u8>,
+    pub error: String,
+    pub quarantined_at: u64,
+}
@@ -0,0 +1,19 @@
+use crate::types::sport::Sports;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+// * self-declared level, the event canister starts the skill rating of a
+// * sport from it before the user played any match
+#[derive(Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum SkillLevel {
+    Beginner,
+    Intermediate,
+    Advanced,
+    Expert,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize)]
+pub struct SportLevel {
+    pub sport: Sports,
+    pub level: SkillLevel,
+}
@@ -1,6 +1,7 @@
 use crate::types::activity::UserActivity;
-use crate::types::notification::Notification;
-use crate::types::{city::CityData, governorate::GovernorateData, sport::Sports};
+use crate::types::{
+    city::CityData, governorate::GovernorateData, skill_level::SportLevel, sport::Sports,
+};
 use candid::CandidType;
 use candid::Principal;
 use serde::{Deserialize, Serialize};
@@ -23,12 +24,12 @@ pub struct User {
     pub avatar_url: Option<Vec<u8>>,
     pub sports: Vec<Sports>,
     pub role: UserRole,
-    pub free_days: Option<Vec<u8>>,
+    pub free_days: Option<Vec<u8>>, // days of the week, 0 = Sunday to 6 = Saturday
     pub is_online: bool,
-    pub notifications: Vec<Notification>,
     pub last_active: u64,
     pub activity: Vec<UserActivity>,
     pub manual_status: bool, // if the user set their status manually e.g. offline
+    pub skill_levels: Option<Vec<SportLevel>>, // self-declared, one per sport
 }
 
 //  * RegisteringUser struct for user registration data
@@ -41,6 +42,7 @@ pub struct RegisteringUser {
     pub avatar_url: Option<Vec<u8>>,
     pub sports: Vec<Sports>,
     pub free_days: Option<Vec<u8>>,
+    pub skill_levels: Option<Vec<SportLevel>>,
 }
 
 // * UpdatingUser struct for user registration data
@@ -54,6 +56,7 @@ pub struct UpdatingUser {
     pub avatar_url: Option<Vec<u8>>,
     pub sports: Vec<Sports>,
     pub free_days: Option<Vec<u8>>,
+    pub skill_levels: Option<Vec<SportLevel>>,
 }
 
 // * PublicUser struct for user general information
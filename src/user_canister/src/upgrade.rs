@@ -0,0 +1,69 @@
+use crate::storage::{
+    quarantine_undecodable_records, NEXT_ID, NOTIFICATIONS, NOTIFICATION_RETENTION, QUARANTINE,
+    SCHEMA_VERSION, USERS,
+};
+use crate::types::notification::Notification;
+
+// * bump this and add a step to `migrate` whenever a stored shape (e.g. `User`) changes
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+pub fn init() {
+    set_schema_version(CURRENT_SCHEMA_VERSION);
+}
+
+pub fn pre_upgrade() {
+    // * all state is in stable structures, only make sure it is readable
+    validate_memory_layout();
+}
+
+pub fn post_upgrade() {
+    // * has to run before the typed maps are opened below
+    let quarantined = quarantine_undecodable_records();
+    if quarantined > 0 {
+        ic_cdk::println!("Quarantined {} undecodable records", quarantined);
+    }
+
+    validate_memory_layout();
+
+    let stored_version = SCHEMA_VERSION.with(|version| *version.borrow().get());
+    if stored_version > CURRENT_SCHEMA_VERSION {
+        ic_cdk::api::trap(format!(
+            "Cannot downgrade schema from version {} to {}",
+            stored_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    for version in stored_version..CURRENT_SCHEMA_VERSION {
+        migrate(version);
+    }
+    set_schema_version(CURRENT_SCHEMA_VERSION);
+}
+
+// * migrate stored data from `version` to `version + 1`
+fn migrate(version: u32) {
+    match version {
+        // * notifications used to live inside `User`, move them to the inbox store
+        0 => Notification::migrate_legacy(),
+        // * records are now written in the versioned envelope (see the `versioned` crate),
+        // * bare Candid records are still read as version 0
+        1 => {}
+        _ => ic_cdk::api::trap(format!("No migration from schema version {}", version)),
+    }
+}
+
+// * loading every stable structure checks its header, a mismatched
+// * memory layout traps here and the upgrade is rolled back
+fn validate_memory_layout() {
+    USERS.with(|users| users.borrow().len());
+    NOTIFICATIONS.with(|notifications| notifications.borrow().len());
+    NOTIFICATION_RETENTION.with(|retention| *retention.borrow().get());
+    QUARANTINE.with(|quarantine| quarantine.borrow().len());
+    NEXT_ID.with(|next_id| *next_id.borrow().get());
+    SCHEMA_VERSION.with(|version| *version.borrow().get());
+}
+
+fn set_schema_version(version: u32) {
+    SCHEMA_VERSION.with(|cell| {
+        cell.borrow_mut().set(version);
+    });
+}
@@ -0,0 +1,287 @@
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Memory, StableBTreeMap, Storable};
+use serde::{de::DeserializeOwned, Serialize};
+use std::borrow::Cow;
+
+// Stored records are a version byte followed by the payload. Records written
+// before the envelope are a bare payload and are read as version 0: Candid
+// ones start with the "DIDL" magic and JSON ones with b'{', so no version
+// may be b'D' or b'{'.
+const CANDID_MAGIC: &[u8] = b"DIDL";
+const JSON_OBJECT_START: u8 = b'{';
+
+// Encoding of the payload, the owners canister stores JSON
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Candid,
+    Json,
+}
+
+pub trait Versioned: CandidType + Serialize + DeserializeOwned + Sized {
+    // Version written by `encode`, bump it whenever the stored shape changes
+    const VERSION: u8;
+    const FORMAT: Format = Format::Candid;
+
+    // Decode a payload written by an older version. The default reads it as
+    // the current shape, which is enough when only `Option` fields were added
+    fn upgrade(version: u8, payload: &[u8]) -> Result<Self, String> {
+        decode_payload(payload)
+            .map_err(|e| format!("cannot upgrade from version {}: {}", version, e))
+    }
+}
+
+fn decode_payload<T: Versioned>(payload: &[u8]) -> Result<T, String> {
+    match T::FORMAT {
+        Format::Candid => Decode!(payload, T).map_err(|e| e.to_string()),
+        Format::Json => serde_json::from_slice(payload).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn encode<T: Versioned>(value: &T) -> Vec<u8> {
+    let mut bytes = vec![T::VERSION];
+    match T::FORMAT {
+        Format::Candid => bytes.extend(Encode!(value).expect("Failed to encode record")),
+        Format::Json => bytes.extend(serde_json::to_vec(value).expect("Failed to encode record")),
+    }
+    bytes
+}
+
+pub fn decode<T: Versioned>(bytes: &[u8]) -> Result<T, String> {
+    let (version, payload) = split(bytes);
+    if version == T::VERSION {
+        decode_payload(payload)
+    } else if version < T::VERSION {
+        T::upgrade(version, payload)
+    } else {
+        Err(format!(
+            "record version {} is newer than the supported version {}",
+            version,
+            T::VERSION
+        ))
+    }
+}
+
+// Used by `Storable::from_bytes`, undecodable records are quarantined on
+// upgrade so reaching the trap here means the stored data is corrupted
+pub fn decode_or_trap<T: Versioned>(bytes: &[u8]) -> T {
+    decode(bytes).unwrap_or_else(|e| {
+        ic_cdk::api::trap(format!(
+            "Failed to decode {}: {}",
+            std::any::type_name::<T>(),
+            e
+        ))
+    })
+}
+
+pub fn split(bytes: &[u8]) -> (u8, &[u8]) {
+    if bytes.starts_with(CANDID_MAGIC) {
+        return (0, bytes);
+    }
+    match bytes.split_first() {
+        Some((&JSON_OBJECT_START, _)) | None => (0, bytes),
+        Some((version, payload)) => (*version, payload),
+    }
+}
+
+// Undecoded view of a stored value
+struct RawRecord(Vec<u8>);
+
+impl Storable for RawRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        RawRecord(bytes.into_owned())
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+// Stored value removed by `quarantine_undecodable`, the canisters keep it in
+// their quarantine store
+#[derive(Debug, Clone, PartialEq)]
+pub struct Undecodable {
+    pub store: String,
+    pub key: Vec<u8>,
+    pub bytes: Vec<u8>,
+    pub error: String,
+}
+
+// Remove the values of `memory` that no longer decode as `T` and return them.
+// This opens its own view of the map, so it must run before the typed map
+// over the same memory is first used (i.e. first thing in `post_upgrade`)
+pub fn quarantine_undecodable<K, T, M>(store: &str, memory: M) -> Vec<Undecodable>
+where
+    K: Storable + Ord + Clone,
+    T: Versioned,
+    M: Memory,
+{
+    let mut records: StableBTreeMap<K, RawRecord, M> = StableBTreeMap::init(memory);
+
+    let undecodable: Vec<(K, Vec<u8>, String)> = records
+        .iter()
+        .filter_map(|entry| {
+            let bytes = entry.value().0;
+            decode::<T>(&bytes)
+                .err()
+                .map(|error| (entry.key().clone(), bytes, error))
+        })
+        .collect();
+
+    undecodable
+        .into_iter()
+        .map(|(key, bytes, error)| {
+            records.remove(&key);
+            Undecodable {
+                store: store.to_string(),
+                key: key.into_bytes(),
+                bytes,
+                error,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Deserialize;
+    use ic_stable_structures::VectorMemory;
+
+    #[derive(Debug, Clone, PartialEq, CandidType, Serialize, Deserialize)]
+    struct Record {
+        name: String,
+        count: u32,
+        note: Option<String>,
+    }
+
+    impl Versioned for Record {
+        const VERSION: u8 = 1;
+    }
+
+    impl Storable for Record {
+        fn to_bytes(&self) -> Cow<'_, [u8]> {
+            Cow::Owned(encode(self))
+        }
+
+        fn from_bytes(bytes: Cow<[u8]>) -> Self {
+            decode_or_trap(&bytes)
+        }
+
+        const BOUND: Bound = Bound::Unbounded;
+
+        fn into_bytes(self) -> Vec<u8> {
+            encode(&self)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, CandidType, Serialize, Deserialize)]
+    struct JsonRecord {
+        name: String,
+        count: u32,
+    }
+
+    impl Versioned for JsonRecord {
+        const VERSION: u8 = 1;
+        const FORMAT: Format = Format::Json;
+    }
+
+    // Shape of `Record` before `note` was added
+    #[derive(CandidType, Serialize)]
+    struct RecordV0 {
+        name: String,
+        count: u32,
+    }
+
+    fn record() -> Record {
+        Record {
+            name: "tal3a".to_string(),
+            count: 3,
+            note: Some("note".to_string()),
+        }
+    }
+
+    #[test]
+    fn round_trips_current_envelope() {
+        let bytes = encode(&record());
+        assert_eq!(bytes[0], Record::VERSION);
+        assert_eq!(decode::<Record>(&bytes), Ok(record()));
+    }
+
+    #[test]
+    fn reads_bare_candid_as_version_zero() {
+        let old = RecordV0 {
+            name: "tal3a".to_string(),
+            count: 3,
+        };
+        let bytes = Encode!(&old).unwrap();
+        assert!(bytes.starts_with(CANDID_MAGIC));
+        assert_eq!(split(&bytes), (0, bytes.as_slice()));
+
+        let decoded = decode::<Record>(&bytes).unwrap();
+        assert_eq!(decoded.name, "tal3a");
+        assert_eq!(decoded.count, 3);
+        assert_eq!(decoded.note, None);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut bytes = encode(&record());
+        bytes[0] = Record::VERSION + 1;
+        assert!(decode::<Record>(&bytes).unwrap_err().contains("newer"));
+    }
+
+    #[test]
+    fn round_trips_json_envelope() {
+        let value = JsonRecord {
+            name: "owner".to_string(),
+            count: 1,
+        };
+        let bytes = encode(&value);
+        assert_eq!(bytes[0], JsonRecord::VERSION);
+        assert_eq!(bytes[1], JSON_OBJECT_START);
+        assert_eq!(decode::<JsonRecord>(&bytes), Ok(value));
+    }
+
+    #[test]
+    fn reads_bare_json_as_version_zero() {
+        let bytes = br#"{"name":"owner","count":7}"#;
+        assert_eq!(split(bytes), (0, &bytes[..]));
+        assert_eq!(
+            decode::<JsonRecord>(bytes),
+            Ok(JsonRecord {
+                name: "owner".to_string(),
+                count: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn quarantines_corrupt_records() {
+        let memory = VectorMemory::default();
+        let mut records: StableBTreeMap<u64, Record, _> = StableBTreeMap::init(memory.clone());
+        records.insert(1, record());
+        records.insert(3, record());
+        drop(records);
+
+        let mut raw: StableBTreeMap<u64, RawRecord, _> = StableBTreeMap::init(memory.clone());
+        raw.insert(2, RawRecord(vec![Record::VERSION, 0xff, 0x00]));
+        drop(raw);
+
+        let quarantined = quarantine_undecodable::<u64, Record, _>("records", memory.clone());
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].store, "records");
+        assert_eq!(quarantined[0].key, 2_u64.into_bytes());
+        assert_eq!(quarantined[0].bytes, vec![Record::VERSION, 0xff, 0x00]);
+
+        // The typed map only holds decodable records now
+        let records: StableBTreeMap<u64, Record, _> = StableBTreeMap::init(memory);
+        let values: Vec<Record> = records.iter().map(|entry| entry.value()).collect();
+        assert_eq!(values, vec![record(), record()]);
+    }
+}